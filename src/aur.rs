@@ -0,0 +1,215 @@
+use std::time::Duration;
+
+use serde::Deserialize;
+
+use crate::{db::desc::timestamp_to_date, error::AppError};
+
+///default `AurUrl`
+pub const AUR_URL: &str = "https://aur.archlinux.org";
+///names per info request, so the url stays short enough
+const INFO_CHUNK: usize = 100;
+
+/// A package as described by the AUR RPC v5 `info` and `search` results
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct AurPackage {
+    pub name: String,
+    pub version: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub maintainer: Option<String>,
+    #[serde(default)]
+    pub num_votes: u32,
+    ///unix time it was flagged out of date
+    #[serde(default)]
+    pub out_of_date: Option<i64>,
+}
+
+impl AurPackage {
+    /// `42 votes, maintained by someone`, and when it was flagged out of date
+    pub fn summary(&self) -> String {
+        let mut parts = vec![format!("{} votes", self.num_votes)];
+        parts.push(match &self.maintainer {
+            Some(maintainer) => format!("maintained by {maintainer}"),
+            None => "orphaned".to_string(),
+        });
+        if let Some(flagged) = self.out_of_date {
+            let date = timestamp_to_date(&flagged.to_string()).unwrap_or_default();
+            parts.push(format!(
+                "out of date since {}",
+                date.get(..10).unwrap_or(&date)
+            ));
+        }
+        parts.join(", ")
+    }
+}
+
+#[derive(Deserialize)]
+struct RpcResponse {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    error: Option<String>,
+    #[serde(default)]
+    results: Vec<AurPackage>,
+}
+
+/// Parse an RPC response, which reports errors with `"type": "error"`
+pub fn parse_response(body: &str) -> Result<Vec<AurPackage>, AppError> {
+    let response: RpcResponse = serde_json::from_str(body)
+        .map_err(|e| AppError::Other(format!("Invalid AUR response: {e}")))?;
+    if response.kind == "error" {
+        let error = response.error.unwrap_or_default();
+        return Err(AppError::Other(format!("AUR error: {error}")));
+    }
+    Ok(response.results)
+}
+
+///percent encode everything but unreserved characters, package names may contain `+`
+fn encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (b as char).to_string()
+            }
+            b => format!("%{b:02X}"),
+        })
+        .collect()
+}
+
+/// Client for the AUR RPC, the base url can point at a mirror or a local mock
+#[derive(Debug, Clone)]
+pub struct AurClient {
+    base_url: String,
+    agent: ureq::Agent,
+}
+
+impl AurClient {
+    pub fn new(base_url: &str) -> Self {
+        let agent = ureq::Agent::config_builder()
+            .timeout_global(Some(Duration::from_secs(15)))
+            .build()
+            .into();
+        Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            agent,
+        }
+    }
+
+    fn get(&self, path_and_query: &str) -> Result<Vec<AurPackage>, AppError> {
+        let url = format!("{}/rpc/v5/{path_and_query}", self.base_url);
+        let body = self
+            .agent
+            .get(&url)
+            .call()
+            .and_then(|mut r| r.body_mut().read_to_string())
+            .map_err(|e| AppError::Other(format!("AUR request failed: {e}")))?;
+        parse_response(&body)
+    }
+
+    /// Details of the named packages, those not in the AUR are left out
+    pub fn info(&self, names: &[String]) -> Result<Vec<AurPackage>, AppError> {
+        let mut found = vec![];
+        for chunk in names.chunks(INFO_CHUNK) {
+            let query = chunk
+                .iter()
+                .map(|n| format!("arg%5B%5D={}", encode(n)))
+                .collect::<Vec<_>>()
+                .join("&");
+            found.extend(self.get(&format!("info?{query}"))?);
+        }
+        Ok(found)
+    }
+
+    /// Packages with `term` in their name or description
+    pub fn search(&self, term: &str) -> Result<Vec<AurPackage>, AppError> {
+        self.get(&format!("search/{}?by=name-desc", encode(term)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        io::{BufRead, BufReader, Write},
+        net::TcpListener,
+    };
+
+    const RESPONSE: &str = r#"{"resultcount":1,"results":[{"Name":"which-git","Version":"2.23-1","Description":"Shows the full path","Maintainer":null,"NumVotes":12,"OutOfDate":1718000000,"Popularity":0.5}],"type":"multiinfo","version":5}"#;
+
+    ///answer one request with `body`, returning the request line
+    fn mock(body: &'static str) -> (String, std::thread::JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let handle = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut request = String::new();
+            reader.read_line(&mut request).unwrap();
+            let mut line = String::new();
+            while reader.read_line(&mut line).unwrap() > 2 {
+                line.clear();
+            }
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            )
+            .unwrap();
+            request
+        });
+        (url, handle)
+    }
+
+    #[test]
+    fn test_parse_response() {
+        let packages = parse_response(RESPONSE).unwrap();
+        assert_eq!(
+            packages,
+            vec![AurPackage {
+                name: "which-git".to_string(),
+                version: "2.23-1".to_string(),
+                description: Some("Shows the full path".to_string()),
+                maintainer: None,
+                num_votes: 12,
+                out_of_date: Some(1718000000),
+            }]
+        );
+        let error = parse_response(
+            r#"{"error":"Incorrect request type specified.","resultcount":0,"results":[],"type":"error","version":5}"#,
+        );
+        assert!(error.unwrap_err().to_string().contains("Incorrect request"));
+        assert!(parse_response("<html>").is_err());
+        assert!(
+            packages[0]
+                .summary()
+                .starts_with("12 votes, orphaned, out of date since 2024-06-")
+        );
+        assert_eq!(encode("gtk+ 3"), "gtk%2B%203");
+    }
+
+    #[test]
+    fn test_client() {
+        let (url, request) = mock(RESPONSE);
+        let client = AurClient::new(&format!("{url}/"));
+        let found = client
+            .info(&["which-git".to_string(), "c++utils".to_string()])
+            .unwrap();
+        assert_eq!(found[0].version, "2.23-1");
+        assert_eq!(
+            request.join().unwrap().trim(),
+            "GET /rpc/v5/info?arg%5B%5D=which-git&arg%5B%5D=c%2B%2Butils HTTP/1.1"
+        );
+
+        let (url, request) = mock(r#"{"resultcount":0,"results":[],"type":"search","version":5}"#);
+        assert!(AurClient::new(&url).search("which").unwrap().is_empty());
+        assert!(
+            request
+                .join()
+                .unwrap()
+                .starts_with("GET /rpc/v5/search/which?by=name-desc ")
+        );
+    }
+}
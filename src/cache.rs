@@ -0,0 +1,259 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::version::vercmp;
+
+/// A package file in one of the pacman caches
+#[derive(Debug, Clone, PartialEq)]
+pub struct CachedPackage {
+    pub name: String,
+    ///`pkgver-pkgrel`, with the epoch if there is one
+    pub version: String,
+    pub arch: String,
+    pub path: PathBuf,
+    pub size: u64,
+    ///local date the file was last modified, `YYYY-MM-DD`
+    pub date: String,
+}
+
+/// Which cached files a cleanup removes
+#[derive(Debug, Clone, PartialEq)]
+pub enum CleanPolicy {
+    ///keep the newest N versions of every package and arch, like `paccache -rk N`
+    KeepLast(usize),
+    ///every file of packages that are not installed, like `paccache -ruk0`
+    Uninstalled,
+    ///files modified before a `YYYY[-MM[-DD]]` date
+    OlderThan(String),
+}
+
+impl CleanPolicy {
+    /// The files this policy would delete, `cached` sorted as `find_cached` returns it
+    pub fn files<'a>(
+        &self,
+        cached: &'a [CachedPackage],
+        is_installed: impl Fn(&str) -> bool,
+    ) -> Vec<&'a CachedPackage> {
+        match self {
+            CleanPolicy::KeepLast(keep) => cached
+                .iter()
+                .enumerate()
+                .filter(|(i, c)| {
+                    //the rest of this package's files for the same arch are newer
+                    let newer = cached[i + 1..]
+                        .iter()
+                        .take_while(|n| n.name == c.name && n.arch == c.arch)
+                        .count();
+                    newer >= *keep
+                })
+                .map(|(_, c)| c)
+                .collect(),
+            CleanPolicy::Uninstalled => cached.iter().filter(|c| !is_installed(&c.name)).collect(),
+            CleanPolicy::OlderThan(date) => cached
+                .iter()
+                .filter(|c| c.date.as_str() < date.as_str())
+                .collect(),
+        }
+    }
+}
+
+/// Split `bash-5.2.026-2-x86_64.pkg.tar.zst` into name, version and arch.
+/// Signatures and partial downloads are not packages
+pub fn parse_package_filename(file: &str) -> Option<(String, String, String)> {
+    let (stem, ext) = file.split_once(".pkg.tar")?;
+    if ext.ends_with(".sig") || ext.ends_with(".part") {
+        return None;
+    }
+    let mut parts = stem.rsplitn(4, '-');
+    let arch = parts.next()?;
+    let pkgrel = parts.next()?;
+    let pkgver = parts.next()?;
+    let name = parts.next()?;
+    if name.is_empty() || pkgver.is_empty() || pkgrel.is_empty() {
+        return None;
+    }
+    Some((
+        name.to_string(),
+        format!("{pkgver}-{pkgrel}"),
+        arch.to_string(),
+    ))
+}
+
+/// Package files in the cache directories, by name and arch and then oldest version first.
+/// Missing directories are skipped
+pub fn find_cached(dirs: &[PathBuf]) -> Vec<CachedPackage> {
+    let mut found = vec![];
+    for dir in dirs {
+        let Ok(entries) = fs::read_dir(dir) else {
+            continue;
+        };
+        for entry in entries.filter_map(|e| e.ok()) {
+            let file = entry.file_name().to_string_lossy().to_string();
+            let Some((name, version, arch)) = parse_package_filename(&file) else {
+                continue;
+            };
+            let Ok(meta) = entry.metadata() else {
+                continue;
+            };
+            let date = meta
+                .modified()
+                .ok()
+                .and_then(|m| jiff::Timestamp::try_from(m).ok())
+                .map(|t| t.to_zoned(jiff::tz::TimeZone::system()).date().to_string())
+                .unwrap_or_default();
+            found.push(CachedPackage {
+                name,
+                version,
+                arch,
+                path: entry.path(),
+                size: meta.len(),
+                date,
+            });
+        }
+    }
+    found.sort_by(|a, b| {
+        a.name
+            .cmp(&b.name)
+            .then_with(|| a.arch.cmp(&b.arch))
+            .then_with(|| vercmp(&a.version, &b.version))
+            .then_with(|| a.path.cmp(&b.path))
+    });
+    found
+}
+
+/// The files to remove for `cached`, with the signatures pacman keeps next to them
+pub fn with_signatures(cached: &[&CachedPackage]) -> Vec<PathBuf> {
+    let mut paths = vec![];
+    for c in cached {
+        paths.push(c.path.clone());
+        let sig = signature_path(&c.path);
+        if sig.exists() {
+            paths.push(sig);
+        }
+    }
+    paths
+}
+
+fn signature_path(path: &Path) -> PathBuf {
+    let mut sig = path.as_os_str().to_owned();
+    sig.push(".sig");
+    PathBuf::from(sig)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_package_filename() {
+        assert_eq!(
+            parse_package_filename("bash-5.2.026-2-x86_64.pkg.tar.zst"),
+            Some((
+                "bash".to_string(),
+                "5.2.026-2".to_string(),
+                "x86_64".to_string()
+            ))
+        );
+        assert_eq!(
+            parse_package_filename("lib32-gcc-libs-1:14.1.1+r58-1-x86_64.pkg.tar.xz")
+                .map(|p| (p.0, p.1)),
+            Some(("lib32-gcc-libs".to_string(), "1:14.1.1+r58-1".to_string()))
+        );
+        assert_eq!(
+            parse_package_filename("bash-5.2.026-2-x86_64.pkg.tar.zst.sig"),
+            None
+        );
+        assert_eq!(
+            parse_package_filename("bash-5.2.026-2-x86_64.pkg.tar.zst.part"),
+            None
+        );
+        assert_eq!(parse_package_filename("download-x86_64.pkg.tar.zst"), None);
+        assert_eq!(parse_package_filename("notes.txt"), None);
+    }
+
+    #[test]
+    fn test_find_cached() {
+        let dir = std::env::temp_dir().join(format!("ms-cache-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        for name in [
+            "nano-7.2-2-x86_64.pkg.tar.zst",
+            "nano-7.2-10-x86_64.pkg.tar.zst",
+            "nano-7.2-10-x86_64.pkg.tar.zst.sig",
+            "bash-5.2.026-2-x86_64.pkg.tar.zst",
+            "nano-7.2-1-i686.pkg.tar.zst",
+        ] {
+            fs::write(dir.join(name), "data").unwrap();
+        }
+        let found = find_cached(&[dir.clone(), dir.join("missing")]);
+        let names: Vec<(&str, &str)> = found
+            .iter()
+            .map(|c| (c.name.as_str(), c.version.as_str()))
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                ("bash", "5.2.026-2"),
+                ("nano", "7.2-1"),
+                ("nano", "7.2-2"),
+                ("nano", "7.2-10")
+            ]
+        );
+        assert_eq!(found[1].arch, "i686");
+        assert_eq!(found[0].size, 4);
+        assert_eq!(found[0].date.len(), 10);
+        let newest = with_signatures(&[&found[3]]);
+        assert_eq!(newest.len(), 2);
+        assert!(newest[1].to_string_lossy().ends_with(".sig"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_clean_policy() {
+        let file_for = |name: &str, version: &str, arch: &str, date: &str| CachedPackage {
+            name: name.to_string(),
+            version: version.to_string(),
+            arch: arch.to_string(),
+            path: PathBuf::from(format!("{name}-{version}-{arch}.pkg.tar.zst")),
+            size: 1,
+            date: date.to_string(),
+        };
+        let file = |name: &str, version: &str, date: &str| file_for(name, version, "x86_64", date);
+        let cached = vec![
+            file("bash", "5.2.021-1", "2023-11-02"),
+            file("bash", "5.2.026-2", "2024-02-15"),
+            file("bash", "5.2.032-1", "2024-08-01"),
+            file("nano", "7.2-1", "2024-01-10"),
+        ];
+        let versions = |files: Vec<&CachedPackage>| {
+            files.iter().map(|c| c.version.clone()).collect::<Vec<_>>()
+        };
+        let installed = |name: &str| name == "bash";
+        assert_eq!(
+            versions(CleanPolicy::KeepLast(2).files(&cached, installed)),
+            vec!["5.2.021-1"]
+        );
+        assert_eq!(
+            versions(CleanPolicy::KeepLast(0).files(&cached, installed)).len(),
+            4
+        );
+        assert_eq!(
+            versions(CleanPolicy::Uninstalled.files(&cached, installed)),
+            vec!["7.2-1"]
+        );
+        assert_eq!(
+            versions(CleanPolicy::OlderThan("2024-02".to_string()).files(&cached, installed)),
+            vec!["5.2.021-1", "7.2-1"]
+        );
+
+        //each arch keeps its own newest versions
+        let cached = vec![
+            file_for("wine", "9.0-1", "i686", "2024-01-10"),
+            file_for("wine", "9.0-1", "x86_64", "2024-01-10"),
+            file_for("wine", "9.1-1", "x86_64", "2024-02-10"),
+        ];
+        let removed = CleanPolicy::KeepLast(1).files(&cached, installed);
+        assert_eq!(removed, vec![&cached[1]]);
+    }
+}
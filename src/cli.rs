@@ -0,0 +1,237 @@
+use std::path::{Path, PathBuf};
+
+use crate::{
+    error::AppError,
+    graph::{GraphDirection, GraphFormat, GraphOptions, PackageGraph, export_graph, why_paths},
+    source::PackageSource,
+    structs::reason::Reason,
+};
+
+pub const USAGE: &str = "Usage: ms [--fixture <dir|snapshot.json>] [--config <file>] [why <package>]
+       ms [--config <file>] aur <search term>
+       ms [--fixture <dir|snapshot.json>] graph <package>... [--reverse|--both] [--depth <n>] [--no-optional] [--mermaid] [--output <file>]";
+
+/// Command line arguments
+#[derive(Debug, Default, PartialEq)]
+pub struct Args {
+    pub fixture: Option<PathBuf>,
+    ///instead of `~/.config/mspacman/config`
+    pub config: Option<PathBuf>,
+    pub command: Option<CliCommand>,
+}
+
+/// Commands that print to stdout instead of starting the tui
+#[derive(Debug, PartialEq)]
+pub enum CliCommand {
+    Why(String),
+    AurSearch(String),
+    Graph {
+        packages: Vec<String>,
+        options: GraphOptions,
+        output: Option<PathBuf>,
+    },
+}
+
+impl Args {
+    pub fn parse(args: &[String]) -> Result<Self, AppError> {
+        let mut parsed = Args::default();
+        let mut graph: Option<(Vec<String>, GraphOptions, Option<PathBuf>)> = None;
+        let mut it = args.iter();
+        while let Some(arg) = it.next() {
+            match (arg.as_str(), graph.as_mut()) {
+                ("--fixture", _) => {
+                    let path = it
+                        .next()
+                        .ok_or("--fixture needs a directory or snapshot".to_string())?;
+                    parsed.fixture = Some(PathBuf::from(path));
+                }
+                ("--config", _) => {
+                    let path = it.next().ok_or("--config needs a file".to_string())?;
+                    parsed.config = Some(PathBuf::from(path));
+                }
+                ("why", None) => {
+                    let name = it.next().ok_or("why needs a package name".to_string())?;
+                    parsed.command = Some(CliCommand::Why(name.clone()));
+                }
+                ("aur", None) => {
+                    let term = it.next().ok_or("aur needs a search term".to_string())?;
+                    parsed.command = Some(CliCommand::AurSearch(term.clone()));
+                }
+                ("graph", None) => graph = Some(Default::default()),
+                ("--reverse", Some((_, options, _))) => options.direction = GraphDirection::Reverse,
+                ("--both", Some((_, options, _))) => options.direction = GraphDirection::Both,
+                ("--no-optional", Some((_, options, _))) => options.optional = false,
+                ("--mermaid", Some((_, options, _))) => options.format = GraphFormat::Mermaid,
+                ("--depth", Some((_, options, _))) => {
+                    let depth = it
+                        .next()
+                        .and_then(|d| d.parse().ok())
+                        .ok_or("--depth needs a number".to_string())?;
+                    options.depth = Some(depth);
+                }
+                ("--output", Some((_, _, output))) => {
+                    let path = it.next().ok_or("--output needs a file".to_string())?;
+                    *output = Some(PathBuf::from(path));
+                }
+                (name, Some((packages, _, _))) if !name.starts_with('-') => {
+                    packages.push(name.to_string())
+                }
+                _ => return Err(format!("Unknown argument {arg}").into()),
+            }
+        }
+        if let Some((packages, options, output)) = graph {
+            if packages.is_empty() {
+                return Err("graph needs at least one package name".to_string().into());
+            }
+            parsed.command = Some(CliCommand::Graph {
+                packages,
+                options,
+                output,
+            });
+        }
+        Ok(parsed)
+    }
+}
+
+pub fn run_cli(command: &CliCommand, source: &dyn PackageSource) -> Result<String, AppError> {
+    match command {
+        CliCommand::Why(name) => why(name, source),
+        CliCommand::AurSearch(term) => aur_search(term, source),
+        CliCommand::Graph {
+            packages,
+            options,
+            output,
+        } => graph(packages, options, output.as_deref(), source),
+    }
+}
+
+fn graph(
+    roots: &[String],
+    options: &GraphOptions,
+    output: Option<&Path>,
+    source: &dyn PackageSource,
+) -> Result<String, AppError> {
+    let graph = PackageGraph::new(source.installed()?);
+    if let Some(missing) = roots.iter().find(|r| graph.get(r).is_none()) {
+        return Err(format!("{missing} is not installed").into());
+    }
+    let out = export_graph(roots, &graph, options);
+    match output {
+        Some(path) => {
+            std::fs::write(path, out)?;
+            Ok(format!("Wrote {}\n", path.display()))
+        }
+        None => Ok(out),
+    }
+}
+
+fn why(name: &str, source: &dyn PackageSource) -> Result<String, AppError> {
+    let graph = PackageGraph::new(source.installed()?);
+    let Some(pack) = graph.get(name) else {
+        return Err(format!("{name} is not installed").into());
+    };
+    if pack.reason == Reason::Explicit {
+        return Ok(format!("{name} is explicitly installed\n"));
+    }
+    let paths = why_paths(name, &graph);
+    if paths.is_empty() {
+        return Ok(format!(
+            "{name} is not required by any explicitly installed package\n"
+        ));
+    }
+    let mut out = format!(
+        "{name} is required through {} path{}:\n",
+        paths.len(),
+        if paths.len() == 1 { "" } else { "s" }
+    );
+    for path in paths {
+        out.push_str(&format!("  {}\n", path.join(" → ")));
+    }
+    Ok(out)
+}
+
+fn aur_search(term: &str, source: &dyn PackageSource) -> Result<String, AppError> {
+    let mut found = source.aur_search(term)?;
+    if found.is_empty() {
+        return Ok(format!("Nothing in the AUR matches {term}\n"));
+    }
+    found.sort_by(|a, b| b.num_votes.cmp(&a.num_votes).then(a.name.cmp(&b.name)));
+    let mut out = String::new();
+    for pack in found {
+        out.push_str(&format!(
+            "aur/{} {} ({})\n    {}\n",
+            pack.name,
+            pack.version,
+            pack.summary(),
+            pack.description.unwrap_or_default()
+        ));
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::fixture::FixtureSource;
+    use std::path::Path;
+
+    #[test]
+    fn test_args() {
+        let args = |a: &[&str]| Args::parse(&a.iter().map(|s| s.to_string()).collect::<Vec<_>>());
+        assert_eq!(args(&[]).unwrap(), Args::default());
+        assert_eq!(
+            args(&["--fixture", "dir", "--config", "ms.conf", "why", "bash"]).unwrap(),
+            Args {
+                fixture: Some(PathBuf::from("dir")),
+                config: Some(PathBuf::from("ms.conf")),
+                command: Some(CliCommand::Why("bash".to_string()))
+            }
+        );
+        assert_eq!(
+            args(&["graph", "a", "b", "--both", "--depth", "2", "--mermaid"]).unwrap(),
+            Args {
+                fixture: None,
+                config: None,
+                command: Some(CliCommand::Graph {
+                    packages: vec!["a".to_string(), "b".to_string()],
+                    options: GraphOptions {
+                        direction: GraphDirection::Both,
+                        depth: Some(2),
+                        optional: true,
+                        format: GraphFormat::Mermaid,
+                    },
+                    output: None,
+                })
+            }
+        );
+        assert!(args(&["why"]).is_err());
+        assert!(args(&["aur"]).is_err());
+        assert!(args(&["--config"]).is_err());
+        assert!(args(&["graph"]).is_err());
+        assert!(args(&["graph", "a", "--depth", "x"]).is_err());
+        assert!(args(&["--reverse"]).is_err());
+        assert!(args(&["--nope"]).is_err());
+    }
+
+    #[test]
+    fn test_why() {
+        let source = FixtureSource::new(&Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures"));
+        let why = |name: &str| run_cli(&CliCommand::Why(name.to_string()), &source);
+        assert_eq!(why("glibc").unwrap(), "glibc is explicitly installed\n");
+        assert_eq!(
+            why("bash").unwrap(),
+            "bash is required through 1 path:\n  which → bash\n"
+        );
+        assert!(why("nope").is_err());
+    }
+
+    #[test]
+    fn test_aur_search() {
+        let source = FixtureSource::new(&Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures"));
+        let search = |term: &str| run_cli(&CliCommand::AurSearch(term.to_string()), &source);
+        let found = search("which").unwrap();
+        assert!(found.starts_with("aur/which 2.23-1 (42 votes, maintained by someone)\n"));
+        assert!(found.contains("aur/which-git 2.23.r4-1 (1 votes, orphaned, out of date since"));
+        assert_eq!(search("zzz").unwrap(), "Nothing in the AUR matches zzz\n");
+    }
+}
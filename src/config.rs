@@ -0,0 +1,252 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{aur::AUR_URL, error::AppError};
+
+/// How to run commands that need root
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum Escalation {
+    #[default]
+    Sudo,
+    Doas,
+    Run0,
+    Pkexec,
+    ///run as is, e.g. when mspacman itself is run as root
+    None,
+}
+
+impl Escalation {
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "sudo" => Some(Escalation::Sudo),
+            "doas" => Some(Escalation::Doas),
+            "run0" => Some(Escalation::Run0),
+            "pkexec" => Some(Escalation::Pkexec),
+            "none" => Some(Escalation::None),
+            _ => None,
+        }
+    }
+
+    /// The program the command is prefixed with
+    pub fn program(&self) -> Option<&'static str> {
+        match self {
+            Escalation::Sudo => Some("sudo"),
+            Escalation::Doas => Some("doas"),
+            Escalation::Run0 => Some("run0"),
+            Escalation::Pkexec => Some("pkexec"),
+            Escalation::None => None,
+        }
+    }
+}
+
+/// External program for foreign packages, such as those from the AUR
+#[derive(Debug, Clone, PartialEq)]
+pub enum AurHelper {
+    Paru,
+    Yay,
+    Aura,
+    ///any other command, `{packages}` is replaced by the package names
+    Template(String),
+}
+
+impl AurHelper {
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "paru" => Some(AurHelper::Paru),
+            "yay" => Some(AurHelper::Yay),
+            "aura" => Some(AurHelper::Aura),
+            v if v.contains("{packages}") => Some(AurHelper::Template(v.to_string())),
+            _ => None,
+        }
+    }
+
+    fn command(&self, args: &[&str], packages: &[String]) -> (String, Vec<String>) {
+        let program = match self {
+            AurHelper::Paru => "paru",
+            AurHelper::Yay => "yay",
+            AurHelper::Aura => "aura",
+            AurHelper::Template(template) => {
+                let mut words = template.split_whitespace();
+                let program = words.next().unwrap_or_default().to_string();
+                let mut expanded = vec![];
+                for word in words {
+                    match word {
+                        "{packages}" => expanded.extend(packages.iter().cloned()),
+                        w => expanded.push(w.to_string()),
+                    }
+                }
+                return (program, expanded);
+            }
+        };
+        let mut expanded: Vec<String> = args.iter().map(|a| a.to_string()).collect();
+        expanded.extend(packages.iter().cloned());
+        (program.to_string(), expanded)
+    }
+
+    /// Program and arguments to install or update `packages`
+    pub fn install(&self, packages: &[String]) -> (String, Vec<String>) {
+        match self {
+            AurHelper::Aura => self.command(&["-A"], packages),
+            _ => self.command(&["-S"], packages),
+        }
+    }
+
+    /// Program and arguments to update every foreign package, `packages` are the installed ones
+    /// found in the AUR
+    pub fn update(&self, packages: &[String]) -> (String, Vec<String>) {
+        match self {
+            AurHelper::Paru | AurHelper::Yay => self.command(&["-Sua"], &[]),
+            AurHelper::Aura => self.command(&["-Au"], &[]),
+            AurHelper::Template(_) => self.command(&[], packages),
+        }
+    }
+
+    /// Program and arguments to remove `packages` with a pacman `-R` flag.
+    /// A template only installs, so pacman removes those
+    pub fn remove(&self, flag: &str, packages: &[String]) -> Option<(String, Vec<String>)> {
+        match self {
+            AurHelper::Template(_) => None,
+            _ => Some(self.command(&[flag], packages)),
+        }
+    }
+}
+
+/// Settings from the mspacman config file, `Key = Value` lines with `#` comments like pacman.conf
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Config {
+    ///`Escalation`, one of sudo, doas, run0, pkexec or none
+    pub escalation: Escalation,
+    ///`AurHelper`, paru, yay, aura or a command with `{packages}`, for foreign packages
+    pub aur_helper: Option<AurHelper>,
+    ///`AurUrl`, where AUR details of foreign packages are looked up
+    pub aur_url: Option<String>,
+}
+
+impl Config {
+    /// The AUR is only asked about foreign packages once it is used, with `AurUrl` or `AurHelper`
+    pub fn aur_url(&self) -> Option<&str> {
+        match &self.aur_url {
+            Some(url) => Some(url),
+            None if self.aur_helper.is_some() => Some(AUR_URL),
+            None => None,
+        }
+    }
+
+    /// A missing file gives the defaults
+    pub fn read(path: &Path) -> Result<Self, AppError> {
+        match fs::read_to_string(path) {
+            Ok(content) => Self::parse(&content)
+                .map_err(|e| AppError::Other(format!("{}: {e}", path.display()))),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub fn parse(content: &str) -> Result<Self, String> {
+        let mut config = Config::default();
+        for (number, line) in content.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                return Err(format!("line {}: expected Key = Value", number + 1));
+            };
+            let value = value.trim();
+            match key.trim() {
+                "Escalation" => {
+                    config.escalation = Escalation::parse(value).ok_or(format!(
+                        "line {}: unknown Escalation {value}, expected sudo, doas, run0, pkexec or none",
+                        number + 1
+                    ))?
+                }
+                "AurHelper" => {
+                    config.aur_helper = Some(AurHelper::parse(value).ok_or(format!(
+                        "line {}: unknown AurHelper {value}, expected paru, yay, aura or a command with {{packages}}",
+                        number + 1
+                    ))?)
+                }
+                "AurUrl" => config.aur_url = Some(value.to_string()),
+                key => return Err(format!("line {}: unknown setting {key}", number + 1)),
+            }
+        }
+        Ok(config)
+    }
+}
+
+/// `$XDG_CONFIG_HOME/mspacman/config`, or `~/.config/mspacman/config`
+pub fn config_path() -> Option<PathBuf> {
+    let dir = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|d| !d.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|h| PathBuf::from(h).join(".config")))?;
+    Some(dir.join("mspacman").join("config"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_config() {
+        let config = Config::parse("# settings\n\nEscalation = doas # for root\n").unwrap();
+        assert_eq!(config.escalation, Escalation::Doas);
+        assert_eq!(Config::parse("").unwrap(), Config::default());
+        assert_eq!(Config::default().escalation.program(), Some("sudo"));
+        assert!(Config::parse("Escalation = su").is_err());
+        assert!(Config::parse("Colour = red").is_err());
+        assert!(Config::parse("Escalation").is_err());
+
+        let helper = |value: &str| {
+            Config::parse(&format!("AurHelper = {value}"))
+                .unwrap()
+                .aur_helper
+                .unwrap()
+        };
+        let packages = vec!["foo".to_string(), "bar".to_string()];
+        assert_eq!(
+            helper("paru").install(&packages),
+            (
+                "paru".to_string(),
+                vec!["-S".into(), "foo".into(), "bar".into()]
+            )
+        );
+        assert_eq!(
+            helper("aura").update(&packages),
+            ("aura".to_string(), vec!["-Au".to_string()])
+        );
+        let template = helper("pikaur -S --noconfirm {packages}");
+        assert_eq!(
+            template.install(&packages),
+            (
+                "pikaur".to_string(),
+                vec![
+                    "-S".into(),
+                    "--noconfirm".into(),
+                    "foo".into(),
+                    "bar".into()
+                ]
+            )
+        );
+        assert_eq!(template.remove("-R", &packages), None);
+        assert!(Config::parse("AurHelper = pikaur").is_err());
+
+        assert_eq!(Config::default().aur_url(), None);
+        assert_eq!(
+            Config::parse("AurHelper = yay").unwrap().aur_url(),
+            Some(AUR_URL)
+        );
+        assert_eq!(
+            Config::parse("AurUrl = http://localhost:8080")
+                .unwrap()
+                .aur_url(),
+            Some("http://localhost:8080")
+        );
+        assert_eq!(
+            Config::read(Path::new("/nonexistent/mspacman/config")).unwrap(),
+            Config::default()
+        );
+    }
+}
@@ -0,0 +1,87 @@
+use std::{fs, path::Path};
+
+use crate::error::AppError;
+
+///default location of the pacman configuration
+pub const CONF_PATH: &str = "/etc/pacman.conf";
+///default `LogFile`
+pub const LOG_PATH: &str = "/var/log/pacman.log";
+///default `CacheDir`
+pub const CACHE_PATH: &str = "/var/cache/pacman/pkg/";
+
+/// The parts of pacman.conf we care about
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct PacmanConf {
+    ///repositories in the order pacman uses them
+    pub repositories: Vec<String>,
+    ///`LogFile` from `[options]`
+    pub log_file: Option<String>,
+    ///`CacheDir` from `[options]`, it may be given more than once
+    pub cache_dirs: Vec<String>,
+}
+
+impl PacmanConf {
+    pub fn read(path: &Path) -> Result<Self, AppError> {
+        Ok(Self::parse(&fs::read_to_string(path)?))
+    }
+
+    pub fn parse(content: &str) -> Self {
+        let mut conf = PacmanConf::default();
+        let mut in_options = false;
+        for line in content.lines() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if let Some(section) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                in_options = section == "options";
+                if !in_options {
+                    conf.repositories.push(section.to_string());
+                }
+            } else if in_options && let Some((key, value)) = line.split_once('=') {
+                match key.trim() {
+                    "LogFile" => conf.log_file = Some(value.trim().to_string()),
+                    "CacheDir" => conf
+                        .cache_dirs
+                        .extend(value.split_whitespace().map(String::from)),
+                    _ => {}
+                }
+            }
+        }
+        conf
+    }
+
+    pub fn log_file(&self) -> &str {
+        self.log_file.as_deref().unwrap_or(LOG_PATH)
+    }
+
+    /// Each `CacheDir`, the default cache only when none is set, as pacman does
+    pub fn cache_dirs(&self) -> Vec<String> {
+        if self.cache_dirs.is_empty() {
+            return vec![CACHE_PATH.to_string()];
+        }
+        self.cache_dirs.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_conf() {
+        let conf = PacmanConf::parse(
+            "[options]\nHoldPkg = pacman glibc\nLogFile = /tmp/pacman.log\nCacheDir = /var/cache/pacman/pkg\nCacheDir = /srv/cache/\n\n#[testing]\n[core]\nInclude = /etc/pacman.d/mirrorlist\n\n[extra] # comment\n[custom]\nServer = file:///repo\n",
+        );
+        assert_eq!(conf.repositories, vec!["core", "extra", "custom"]);
+        assert_eq!(conf.log_file(), "/tmp/pacman.log");
+        assert_eq!(PacmanConf::parse("").log_file(), LOG_PATH);
+        assert_eq!(
+            conf.cache_dirs(),
+            vec!["/var/cache/pacman/pkg", "/srv/cache/"]
+        );
+        assert_eq!(PacmanConf::parse("").cache_dirs(), vec![CACHE_PATH]);
+        //a relocated cache replaces the default
+        assert_eq!(
+            PacmanConf::parse("[options]\nCacheDir = /srv/cache/\n").cache_dirs(),
+            vec!["/srv/cache/"]
+        );
+    }
+}
@@ -0,0 +1,127 @@
+use std::collections::HashMap;
+
+use crate::{
+    error::AppError,
+    structs::{dependency::Dependency, package::Package, reason::Reason},
+};
+
+/// Parse the `%KEY%` sections of a pacman `desc` or `files` entry
+/// Each section is a header line followed by one value per line, ended by a blank line
+pub fn parse_sections(content: &str) -> HashMap<String, Vec<String>> {
+    let mut sections: HashMap<String, Vec<String>> = HashMap::new();
+    let mut current: Option<String> = None;
+    for line in content.lines() {
+        if line.is_empty() {
+            current = None;
+            continue;
+        }
+        if current.is_none() && line.len() > 2 && line.starts_with('%') && line.ends_with('%') {
+            let key = line[1..line.len() - 1].to_string();
+            sections.entry(key.clone()).or_default();
+            current = Some(key);
+            continue;
+        }
+        if let Some(key) = &current {
+            sections
+                .entry(key.clone())
+                .or_default()
+                .push(line.to_string());
+        }
+    }
+    sections
+}
+
+/// Build a package from the parsed sections of a `desc` entry
+/// Works for both local (`%SIZE%`) and sync (`%ISIZE%`) databases
+pub fn package_from_sections(sections: &HashMap<String, Vec<String>>) -> Package {
+    let single = |key: &str| {
+        sections
+            .get(key)
+            .and_then(|v| v.first())
+            .cloned()
+            .unwrap_or_default()
+    };
+    let list = |key: &str| sections.get(key).cloned().unwrap_or_default();
+    let number = |key: &str| single(key).parse::<usize>().unwrap_or_default();
+
+    let installed_size = if sections.contains_key("SIZE") {
+        number("SIZE")
+    } else {
+        number("ISIZE")
+    };
+
+    Package {
+        name: single("NAME"),
+        version: single("VERSION"),
+        description: single("DESC"),
+        base: single("BASE"),
+        url: single("URL"),
+        architecture: single("ARCH"),
+        packager: single("PACKAGER"),
+        licenses: list("LICENSE"),
+        groups: list("GROUPS"),
+        provides: list("PROVIDES")
+            .iter()
+            .map(|d| Dependency::from(d.as_str()))
+            .collect(),
+        conflicts: list("CONFLICTS")
+            .iter()
+            .map(|d| Dependency::from(d.as_str()))
+            .collect(),
+        replaces: list("REPLACES")
+            .iter()
+            .map(|d| Dependency::from(d.as_str()))
+            .collect(),
+        dependencies: list("DEPENDS")
+            .iter()
+            .map(|d| Dependency::from(d.as_str()))
+            .collect(),
+        dependencies_optional: list("OPTDEPENDS")
+            .iter()
+            .filter_map(|d| d.split(':').next())
+            .map(|d| d.trim().to_string())
+            .collect(),
+        reason: match single("REASON").as_str() {
+            "1" => Reason::Dependency,
+            _ => Reason::Explicit,
+        },
+        validated: list("VALIDATION").iter().any(|v| v == "pgp"),
+        installed: timestamp_to_date(&single("INSTALLDATE")).ok(),
+        build_date: timestamp_to_date(&single("BUILDDATE")).ok(),
+        installed_size,
+        download_size: number("CSIZE"),
+        ..Default::default()
+    }
+}
+
+/// Get the file list from a `files` entry, as absolute paths like `pacman -Ql`
+pub fn files_from_sections(sections: &HashMap<String, Vec<String>>) -> Vec<String> {
+    sections
+        .get("FILES")
+        .map(|files| files.iter().map(|f| format!("/{f}")).collect())
+        .unwrap_or_default()
+}
+
+/// Get the config files pacman keeps on upgrade from the `%BACKUP%` section of a `files` entry.
+/// Each line is a path and its md5 separated by a tab, only the absolute path is returned
+pub fn backup_from_sections(sections: &HashMap<String, Vec<String>>) -> Vec<String> {
+    sections
+        .get("BACKUP")
+        .map(|lines| {
+            lines
+                .iter()
+                .filter_map(|l| l.split('\t').next())
+                .map(|f| format!("/{f}"))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Convert a unix timestamp to local time in the same format as `to_date`
+pub fn timestamp_to_date(value: &str) -> Result<String, AppError> {
+    let secs = value
+        .parse::<i64>()
+        .map_err(|e| AppError::Other(format!("Invalid timestamp {value}: {e}")))?;
+    let time = jiff::Timestamp::from_second(secs)?.to_zoned(jiff::tz::TimeZone::system());
+    Ok(time.datetime().to_string().replace("T", " "))
+}
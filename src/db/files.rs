@@ -0,0 +1,158 @@
+use std::{collections::HashMap, path::Path};
+
+use crate::{
+    db::{
+        desc::{files_from_sections, parse_sections},
+        sync::{read_archive_entries, sync_databases},
+    },
+    error::AppError,
+};
+
+/// The file list of one package in a repository `.files` database
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyncFiles {
+    pub repository: String,
+    pub name: String,
+    pub files: Vec<String>,
+}
+
+/// A file found in the files databases, like a line of `pacman -F`
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileMatch {
+    pub repository: String,
+    pub name: String,
+    pub path: String,
+}
+
+/// Every `.files` database present locally, as downloaded by `pacman -Fy`
+#[derive(Debug, Clone, Default)]
+pub struct FilesDb {
+    packages: Vec<SyncFiles>,
+    ///name to the first repository's package, repositories are in order
+    by_name: HashMap<String, usize>,
+}
+
+impl FilesDb {
+    pub fn new(packages: Vec<SyncFiles>) -> Self {
+        let mut by_name = HashMap::new();
+        for (i, pack) in packages.iter().enumerate() {
+            by_name.entry(pack.name.clone()).or_insert(i);
+        }
+        Self { packages, by_name }
+    }
+
+    /// Read `<db_path>/sync/<repo>.files` for every repository, or all of them if none are given
+    pub fn read(db_path: &Path, repos: &[String]) -> Result<Self, AppError> {
+        let mut packages = vec![];
+        for (repo, path) in sync_databases(db_path, repos, "files")? {
+            let mut entries: Vec<SyncFiles> = read_archive_entries(&path, &["desc", "files"])?
+                .iter()
+                .map(|content| {
+                    let sections = parse_sections(content);
+                    SyncFiles {
+                        repository: repo.clone(),
+                        name: sections
+                            .get("NAME")
+                            .and_then(|n| n.first())
+                            .cloned()
+                            .unwrap_or_default(),
+                        files: files_from_sections(&sections),
+                    }
+                })
+                .filter(|p| !p.name.is_empty())
+                .collect();
+            entries.sort_by(|a, b| a.name.cmp(&b.name));
+            packages.extend(entries);
+        }
+        Ok(Self::new(packages))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.packages.is_empty()
+    }
+
+    /// Files of a package in the first repository that has it
+    pub fn files(&self, name: &str) -> Option<&[String]> {
+        self.by_name
+            .get(name)
+            .map(|&i| self.packages[i].files.as_slice())
+    }
+
+    /// Like `pacman -F`: a query with a slash must match the whole path,
+    /// otherwise it matches the file name in any directory
+    pub fn search(&self, query: &str) -> Vec<FileMatch> {
+        let query = query.trim().trim_end_matches('/');
+        if query.is_empty() {
+            return vec![];
+        }
+        let full_path = query.contains('/');
+        let query = if full_path {
+            format!("/{}", query.trim_start_matches('/'))
+        } else {
+            query.to_string()
+        };
+        let mut matches = vec![];
+        for pack in &self.packages {
+            for file in &pack.files {
+                let file = file.trim_end_matches('/');
+                let found = if full_path {
+                    file == query
+                } else {
+                    file.rsplit('/').next() == Some(query.as_str())
+                };
+                if found {
+                    matches.push(FileMatch {
+                        repository: pack.repository.clone(),
+                        name: pack.name.clone(),
+                        path: file.to_string(),
+                    });
+                }
+            }
+        }
+        matches
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_files_db() {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures");
+        let db = FilesDb::read(&root, &[]).unwrap();
+        assert_eq!(
+            db.files("htop").unwrap(),
+            [
+                "/usr/",
+                "/usr/bin/",
+                "/usr/bin/htop",
+                "/usr/share/",
+                "/usr/share/man/",
+                "/usr/share/man/man1/",
+                "/usr/share/man/man1/htop.1.gz"
+            ]
+        );
+        assert!(db.files("missing").is_none());
+
+        let found = db.search("/usr/bin/zsh");
+        assert_eq!(
+            found,
+            vec![FileMatch {
+                repository: "extra".to_string(),
+                name: "zsh".to_string(),
+                path: "/usr/bin/zsh".to_string(),
+            }]
+        );
+        //bare names match in any directory
+        assert_eq!(db.search("tput")[0].name, "ncurses");
+        assert_eq!(db.search("usr/bin/tput")[0].name, "ncurses");
+        assert!(db.search("bin/tput").is_empty());
+        assert!(db.search("").is_empty());
+
+        //only the listed repositories are read
+        let db = FilesDb::read(&root, &["extra".to_string()]).unwrap();
+        assert!(db.files("bash").is_none());
+        assert!(db.files("zsh").is_some());
+    }
+}
@@ -0,0 +1,159 @@
+use std::{
+    collections::HashMap,
+    fs,
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    db::{
+        desc::{backup_from_sections, files_from_sections, package_from_sections, parse_sections},
+        mtree::{MtreeEntry, parse_mtree},
+    },
+    error::AppError,
+    structs::{dependency::Dependency, package::Package},
+};
+
+/// Read every installed package from `<db_path>/local/<name>-<version>/desc`
+pub fn read_local_packages(db_path: &Path) -> Result<Vec<Package>, AppError> {
+    let mut packs = vec![];
+    for entry in fs::read_dir(db_path.join("local"))? {
+        let entry = entry?;
+        let desc = entry.path().join("desc");
+        if !desc.is_file() {
+            continue; //e.g. ALPM_DB_VERSION
+        }
+        let pack = package_from_sections(&parse_sections(&fs::read_to_string(desc)?));
+        if !pack.name.is_empty() {
+            packs.push(pack);
+        }
+    }
+    compute_required_by(&mut packs);
+    Ok(packs)
+}
+
+/// Read the file list of an installed package from `<db_path>/local/<name>-<version>/files`
+pub fn read_local_files(db_path: &Path, pack: &Package) -> Result<Vec<String>, AppError> {
+    let content = fs::read_to_string(local_entry(db_path, pack).join("files"))?;
+    Ok(files_from_sections(&parse_sections(&content)))
+}
+
+/// Read the backup files of an installed package, from the same `files` entry
+pub fn read_local_backup(db_path: &Path, pack: &Package) -> Result<Vec<String>, AppError> {
+    let content = fs::read_to_string(local_entry(db_path, pack).join("files"))?;
+    Ok(backup_from_sections(&parse_sections(&content)))
+}
+
+/// Read the gzip compressed `<db_path>/local/<name>-<version>/mtree` of an installed package
+pub fn read_local_mtree(db_path: &Path, pack: &Package) -> Result<Vec<MtreeEntry>, AppError> {
+    let path = local_entry(db_path, pack).join("mtree");
+    let file = fs::File::open(&path)
+        .map_err(|e| AppError::Other(format!("No mtree for {}: {e}", pack.name)))?;
+    let mut content = String::new();
+    flate2::read::GzDecoder::new(file).read_to_string(&mut content)?;
+    Ok(parse_mtree(&content))
+}
+
+fn local_entry(db_path: &Path, pack: &Package) -> PathBuf {
+    db_path
+        .join("local")
+        .join(format!("{}-{}", pack.name, pack.version))
+}
+
+/// The local database does not store reverse dependencies, pacman computes them on the fly.
+/// We fill `required_by` and `required_by_optional` the same way, also following provides
+pub fn compute_required_by(packs: &mut [Package]) {
+    //map each name (and provided name) to the packages satisfying it
+    let mut satisfiers: HashMap<String, Vec<usize>> = HashMap::new();
+    for (i, pack) in packs.iter().enumerate() {
+        satisfiers.entry(pack.name.clone()).or_default().push(i);
+        for prov in &pack.provides {
+            let entry = satisfiers.entry(prov.name.clone()).or_default();
+            if !entry.contains(&i) {
+                entry.push(i);
+            }
+        }
+    }
+
+    let mut required_by: Vec<Vec<String>> = vec![vec![]; packs.len()];
+    let mut optional_for: Vec<Vec<String>> = vec![vec![]; packs.len()];
+    for pack in packs.iter() {
+        for dep in &pack.dependencies {
+            for &i in satisfiers.get(&dep.name).into_iter().flatten() {
+                required_by[i].push(pack.name.clone());
+            }
+        }
+        for dep in &pack.dependencies_optional {
+            let name = Dependency::from(dep.as_str()).name;
+            for &i in satisfiers.get(&name).into_iter().flatten() {
+                optional_for[i].push(pack.name.clone());
+            }
+        }
+    }
+
+    for ((pack, mut req), mut opt) in packs.iter_mut().zip(required_by).zip(optional_for) {
+        req.sort();
+        req.dedup();
+        opt.sort();
+        opt.dedup();
+        pack.required_by = req;
+        pack.required_by_optional = opt;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structs::reason::Reason;
+
+    fn fixture() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures")
+    }
+
+    #[test]
+    fn test_read_local() {
+        let packs = read_local_packages(&fixture()).unwrap();
+        assert_eq!(packs.len(), 4);
+
+        let bash = packs.iter().find(|p| p.name == "bash").unwrap();
+        assert_eq!(bash.version, "5.2.026-2");
+        assert_eq!(bash.description, "The GNU Bourne Again shell");
+        assert_eq!(bash.url, "https://www.gnu.org/software/bash/bash.html");
+        assert_eq!(bash.packager, "Arch Packager <packager@archlinux.org>");
+        assert_eq!(bash.licenses, vec!["GPL-3.0-or-later"]);
+        assert_eq!(bash.groups, vec!["base-devel"]);
+        assert_eq!(bash.installed_size, 9453056);
+        assert_eq!(bash.provides, vec![Dependency::from("sh")]);
+        let deps: Vec<String> = bash.dependencies.iter().map(|d| d.to_string()).collect();
+        assert_eq!(deps, vec!["readline", "glibc>=2.38", "ncurses"]);
+        assert_eq!(bash.dependencies_optional, vec!["bash-completion"]);
+        assert_eq!(bash.reason, Reason::Dependency);
+        assert!(bash.validated);
+        assert!(bash.installed.is_some());
+        //required through the `sh` provide
+        assert_eq!(bash.required_by, vec!["which"]);
+
+        let glibc = packs.iter().find(|p| p.name == "glibc").unwrap();
+        assert_eq!(glibc.reason, Reason::Explicit);
+        assert_eq!(glibc.required_by, vec!["bash", "readline"]);
+
+        let which = packs.iter().find(|p| p.name == "which").unwrap();
+        assert!(!which.validated);
+        assert!(which.required_by.is_empty());
+    }
+
+    #[test]
+    fn test_read_files() {
+        let packs = read_local_packages(&fixture()).unwrap();
+        let bash = packs.iter().find(|p| p.name == "bash").unwrap();
+        let files = read_local_files(&fixture(), bash).unwrap();
+        assert_eq!(
+            files,
+            vec!["/usr/", "/usr/bin/", "/usr/bin/bash", "/usr/bin/sh"]
+        );
+        assert_eq!(
+            read_local_backup(&fixture(), bash).unwrap(),
+            vec!["/etc/bash.bashrc"]
+        );
+    }
+}
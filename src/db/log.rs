@@ -0,0 +1,229 @@
+use std::{fmt::Display, fs, path::Path};
+
+use crate::error::AppError;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LogAction {
+    Installed,
+    Reinstalled,
+    Upgraded,
+    Downgraded,
+    Removed,
+}
+
+impl LogAction {
+    fn parse(word: &str) -> Option<Self> {
+        match word {
+            "installed" => Some(LogAction::Installed),
+            "reinstalled" => Some(LogAction::Reinstalled),
+            "upgraded" => Some(LogAction::Upgraded),
+            "downgraded" => Some(LogAction::Downgraded),
+            "removed" => Some(LogAction::Removed),
+            _ => None,
+        }
+    }
+}
+
+impl Display for LogAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            LogAction::Installed => "installed",
+            LogAction::Reinstalled => "reinstalled",
+            LogAction::Upgraded => "upgraded",
+            LogAction::Downgraded => "downgraded",
+            LogAction::Removed => "removed",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// One package change in `pacman.log`
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogEntry {
+    ///`YYYY-MM-DD HH:MM:SS` as logged, older logs only have minutes
+    pub date: String,
+    pub action: LogAction,
+    pub package: String,
+    pub old_version: Option<String>,
+    pub new_version: Option<String>,
+}
+
+/// Changes made by one pacman run, from `transaction started` to `transaction completed`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Transaction {
+    pub date: String,
+    ///the `pacman ...` command line logged before it, if any
+    pub command: Option<String>,
+    pub entries: Vec<LogEntry>,
+}
+
+/// Changes of one package across the log, oldest first
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Timeline {
+    pub entries: Vec<LogEntry>,
+}
+
+impl Timeline {
+    pub fn new(transactions: &[Transaction], package: &str) -> Self {
+        let entries = transactions
+            .iter()
+            .flat_map(|t| &t.entries)
+            .filter(|e| e.package == package)
+            .cloned()
+            .collect();
+        Self { entries }
+    }
+
+    /// When the package was first installed, as far back as the log goes
+    pub fn first_installed(&self) -> Option<&str> {
+        self.entries
+            .iter()
+            .find(|e| e.action == LogAction::Installed)
+            .map(|e| e.date.as_str())
+    }
+
+    /// When the current version was put in place, `None` if the package was removed last
+    pub fn current_since(&self) -> Option<&str> {
+        self.entries
+            .last()
+            .filter(|e| e.action != LogAction::Removed)
+            .map(|e| e.date.as_str())
+    }
+}
+
+pub fn read_log(path: &Path) -> Result<Vec<Transaction>, AppError> {
+    //the log may contain invalid utf8 from scriptlet output
+    let content = String::from_utf8_lossy(&fs::read(path)?).to_string();
+    Ok(parse_log(&content))
+}
+
+/// Group the package changes of a `pacman.log` into transactions, oldest first.
+/// Changes logged outside `transaction started`, as in very old logs, get one of their own
+pub fn parse_log(content: &str) -> Vec<Transaction> {
+    let mut transactions = vec![];
+    let mut current: Option<Transaction> = None;
+    let mut command = None;
+    for line in content.lines() {
+        let Some((date, rest)) = line.strip_prefix('[').and_then(|l| l.split_once("] ")) else {
+            continue;
+        };
+        let date = log_date(date);
+        let Some((source, message)) = rest.strip_prefix('[').and_then(|l| l.split_once("] "))
+        else {
+            continue;
+        };
+        match (source, message) {
+            ("PACMAN", m) if m.starts_with("Running '") => {
+                command = m
+                    .strip_prefix("Running '")
+                    .and_then(|c| c.strip_suffix('\''))
+                    .map(String::from);
+            }
+            ("ALPM", "transaction started") => {
+                transactions.extend(current.take().filter(|t| !t.entries.is_empty()));
+                current = Some(Transaction {
+                    date,
+                    command: command.take(),
+                    entries: vec![],
+                });
+            }
+            ("ALPM", m) if m.starts_with("transaction ") => {
+                //completed, failed or interrupted
+                transactions.extend(current.take().filter(|t| !t.entries.is_empty()));
+            }
+            ("ALPM", m) => {
+                let Some(entry) = parse_entry(&date, m) else {
+                    continue;
+                };
+                current
+                    .get_or_insert_with(|| Transaction {
+                        date: date.clone(),
+                        command: command.take(),
+                        entries: vec![],
+                    })
+                    .entries
+                    .push(entry);
+            }
+            _ => {}
+        }
+    }
+    transactions.extend(current.filter(|t| !t.entries.is_empty()));
+    transactions
+}
+
+///`2024-02-15T12:26:40+0100` becomes `2024-02-15 12:26:40`, the old `2019-01-01 10:00` is kept
+fn log_date(raw: &str) -> String {
+    let date = raw.replacen('T', " ", 1);
+    date.get(..19).unwrap_or(&date).to_string()
+}
+
+///`upgraded bash (5.2.021-1 -> 5.2.026-2)`
+fn parse_entry(date: &str, message: &str) -> Option<LogEntry> {
+    let (action, rest) = message.split_once(' ')?;
+    let action = LogAction::parse(action)?;
+    let (package, versions) = rest.split_once(" (")?;
+    let versions = versions.strip_suffix(')')?;
+    let (old_version, new_version) = match versions.split_once(" -> ") {
+        Some((old, new)) => (Some(old.to_string()), Some(new.to_string())),
+        None if action == LogAction::Removed => (Some(versions.to_string()), None),
+        None => (None, Some(versions.to_string())),
+    };
+    Some(LogEntry {
+        date: date.to_string(),
+        action,
+        package: package.to_string(),
+        old_version,
+        new_version,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_log() {
+        let log =
+            read_log(&Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures/pacman.log")).unwrap();
+        assert_eq!(log.len(), 8);
+        assert_eq!(log[0].command.as_deref(), Some("pacman -S bash"));
+        assert_eq!(log[0].entries.len(), 3);
+        assert_eq!(
+            log[4].entries[0],
+            LogEntry {
+                date: "2024-02-15 12:26:40".to_string(),
+                action: LogAction::Upgraded,
+                package: "bash".to_string(),
+                old_version: Some("5.2.021-1".to_string()),
+                new_version: Some("5.2.026-2".to_string()),
+            }
+        );
+        let removed = &log[7].entries[0];
+        assert_eq!(removed.action, LogAction::Removed);
+        assert_eq!(removed.old_version.as_deref(), Some("7.2-1"));
+        assert_eq!(removed.new_version, None);
+
+        //old format without transactions
+        let old = parse_log(
+            "[2019-01-01 10:00] [PACMAN] Running 'pacman -S foo'\n[2019-01-01 10:00] [ALPM] installed foo (1.0-1)\n[2019-01-01 10:01] [ALPM] reinstalled foo (1.0-1)\n",
+        );
+        assert_eq!(old.len(), 1);
+        assert_eq!(old[0].date, "2019-01-01 10:00");
+        assert_eq!(old[0].entries[1].action, LogAction::Reinstalled);
+    }
+
+    #[test]
+    fn test_timeline() {
+        let log =
+            read_log(&Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures/pacman.log")).unwrap();
+        let nano = Timeline::new(&log, "nano");
+        assert_eq!(nano.entries.len(), 4);
+        assert_eq!(nano.first_installed(), Some("2024-01-10 18:30:02"));
+        assert_eq!(nano.current_since(), None);
+
+        let bash = Timeline::new(&log, "bash");
+        assert_eq!(bash.first_installed(), Some("2023-11-02 09:00:02"));
+        assert_eq!(bash.current_since(), Some("2024-02-15 12:26:40"));
+        assert_eq!(Timeline::new(&log, "missing"), Timeline::default());
+    }
+}
@@ -0,0 +1,21 @@
+use std::path::PathBuf;
+
+pub mod conf;
+pub mod desc;
+pub mod files;
+pub mod local;
+pub mod log;
+pub mod mtree;
+pub mod snapshot;
+pub mod sync;
+
+///default location of the pacman database
+pub const DB_PATH: &str = "/var/lib/pacman";
+
+pub fn db_path() -> PathBuf {
+    PathBuf::from(DB_PATH)
+}
+
+pub fn conf_path() -> PathBuf {
+    PathBuf::from(conf::CONF_PATH)
+}
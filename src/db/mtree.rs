@@ -0,0 +1,144 @@
+use std::collections::HashMap;
+
+/// What an mtree entry describes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EntryType {
+    File,
+    Dir,
+    Link,
+}
+
+/// One path of a package's `.MTREE`, as stored in `local/<name>-<version>/mtree`
+#[derive(Debug, Clone, PartialEq)]
+pub struct MtreeEntry {
+    ///absolute path
+    pub path: String,
+    pub kind: EntryType,
+    pub mode: Option<u32>,
+    pub size: Option<u64>,
+    ///seconds since the epoch
+    pub time: Option<i64>,
+    pub sha256: Option<String>,
+    pub link: Option<String>,
+}
+
+/// Parse the text of an mtree file. `/set` defaults apply to the lines after them,
+/// metadata entries such as `./.PKGINFO` are skipped
+pub fn parse_mtree(content: &str) -> Vec<MtreeEntry> {
+    let mut defaults: HashMap<String, String> = HashMap::new();
+    let mut entries = vec![];
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let mut words = line.split_whitespace();
+        let Some(first) = words.next() else {
+            continue;
+        };
+        match first {
+            "/set" => {
+                for (k, v) in words.filter_map(|w| w.split_once('=')) {
+                    defaults.insert(k.to_string(), v.to_string());
+                }
+                continue;
+            }
+            "/unset" => {
+                for k in words {
+                    defaults.remove(k);
+                }
+                continue;
+            }
+            _ => {}
+        }
+        let Some(path) = first.strip_prefix("./") else {
+            continue;
+        };
+        if path.starts_with('.') {
+            continue; //.PKGINFO, .BUILDINFO, .MTREE, .INSTALL
+        }
+        let mut keys = defaults.clone();
+        for (k, v) in words.filter_map(|w| w.split_once('=')) {
+            keys.insert(k.to_string(), v.to_string());
+        }
+        let kind = match keys.get("type").map(String::as_str) {
+            Some("dir") => EntryType::Dir,
+            Some("link") => EntryType::Link,
+            _ => EntryType::File,
+        };
+        entries.push(MtreeEntry {
+            path: format!("/{}", unescape(path)),
+            kind,
+            mode: keys
+                .get("mode")
+                .and_then(|m| u32::from_str_radix(m, 8).ok()),
+            size: keys.get("size").and_then(|s| s.parse().ok()),
+            //fractional part is ignored, as pacman does
+            time: keys
+                .get("time")
+                .and_then(|t| t.split('.').next())
+                .and_then(|t| t.parse().ok()),
+            sha256: keys.get("sha256digest").cloned(),
+            link: keys.get("link").map(|l| unescape(l)),
+        });
+    }
+    entries
+}
+
+/// mtree escapes special characters such as spaces as `\ooo` octal
+fn unescape(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'\\'
+            && let Some(octal) = value.get(i + 1..i + 4)
+            && let Ok(b) = u8::from_str_radix(octal, 8)
+        {
+            out.push(b);
+            i += 4;
+            continue;
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_mtree() {
+        let content = "#mtree
+/set type=file uid=0 gid=0 mode=644
+./.BUILDINFO time=1707334010.0 size=4873 sha256digest=aa
+./usr time=1707334010.0 mode=755 type=dir
+./usr/bin/bash time=1707334010.5 mode=755 size=1112880 sha256digest=abc
+./usr/bin/sh time=1707334010.0 mode=777 type=link link=bash
+./usr/share/my\\040file time=1707334010.0 size=0
+";
+        let entries = parse_mtree(content);
+        assert_eq!(entries.len(), 4);
+        assert_eq!(entries[0].path, "/usr");
+        assert_eq!(entries[0].kind, EntryType::Dir);
+        assert_eq!(
+            entries[1],
+            MtreeEntry {
+                path: "/usr/bin/bash".to_string(),
+                kind: EntryType::File,
+                mode: Some(0o755),
+                size: Some(1112880),
+                time: Some(1707334010),
+                sha256: Some("abc".to_string()),
+                link: None,
+            }
+        );
+        assert_eq!(entries[2].kind, EntryType::Link);
+        assert_eq!(entries[2].link.as_deref(), Some("bash"));
+        //defaults from /set
+        assert_eq!(entries[3].path, "/usr/share/my file");
+        assert_eq!(entries[3].mode, Some(0o644));
+    }
+}
@@ -0,0 +1,153 @@
+use std::{collections::HashMap, fs, path::Path};
+
+use serde::Deserialize;
+
+use crate::{
+    db::{
+        desc::{backup_from_sections, files_from_sections, package_from_sections},
+        files::{FilesDb, SyncFiles},
+        local::compute_required_by,
+    },
+    error::AppError,
+    structs::package::Package,
+};
+
+///the sections of a `desc` and `files` entry, e.g. `"NAME": ["bash"]`
+type Entry = HashMap<String, Vec<String>>;
+
+/// A repository of a snapshot, in pacman.conf order
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct SnapshotRepo {
+    pub name: String,
+    #[serde(default)]
+    pub packages: Vec<Entry>,
+}
+
+/// The pacman database in one JSON file:
+/// `{"local": [entry], "sync": [{"name": "core", "packages": [entry]}]}`.
+/// Each entry maps the `%KEY%` sections of its `desc` and `files` to their lines
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Snapshot {
+    #[serde(default)]
+    pub local: Vec<Entry>,
+    #[serde(default)]
+    pub sync: Vec<SnapshotRepo>,
+}
+
+impl Snapshot {
+    pub fn read(path: &Path) -> Result<Self, AppError> {
+        Self::parse(&fs::read_to_string(path)?)
+            .map_err(|e| AppError::Other(format!("{}: {e}", path.display())))
+    }
+
+    pub fn parse(content: &str) -> Result<Self, String> {
+        serde_json::from_str(content).map_err(|e| e.to_string())
+    }
+
+    /// Installed packages with their reverse dependencies, like `read_local_packages`
+    pub fn installed(&self) -> Vec<Package> {
+        let mut packs: Vec<Package> = self
+            .local
+            .iter()
+            .map(package_from_sections)
+            .filter(|p| !p.name.is_empty())
+            .collect();
+        compute_required_by(&mut packs);
+        packs
+    }
+
+    /// Repository packages tagged with their repository, like `read_sync_packages`
+    pub fn sync(&self) -> Vec<Package> {
+        self.sync
+            .iter()
+            .flat_map(|repo| {
+                repo.packages.iter().map(|entry| Package {
+                    repository: Some(repo.name.clone()),
+                    ..package_from_sections(entry)
+                })
+            })
+            .filter(|p| !p.name.is_empty())
+            .collect()
+    }
+
+    fn local_entry(&self, pack: &Package) -> Result<&Entry, AppError> {
+        self.local
+            .iter()
+            .find(|e| e.get("NAME").and_then(|n| n.first()) == Some(&pack.name))
+            .ok_or(AppError::Other(format!(
+                "{} is not in the snapshot",
+                pack.name
+            )))
+    }
+
+    /// File list of an installed package
+    pub fn files(&self, pack: &Package) -> Result<Vec<String>, AppError> {
+        Ok(files_from_sections(self.local_entry(pack)?))
+    }
+
+    /// Backup files of every installed package that has some
+    pub fn backups(&self) -> Vec<(String, Vec<String>)> {
+        self.installed()
+            .into_iter()
+            .filter_map(|pack| {
+                let files = backup_from_sections(self.local_entry(&pack).ok()?);
+                (!files.is_empty()).then_some((pack.name, files))
+            })
+            .collect()
+    }
+
+    /// File lists of the repository packages that have a `FILES` section
+    pub fn sync_files(&self) -> FilesDb {
+        let packages = self
+            .sync
+            .iter()
+            .flat_map(|repo| {
+                repo.packages
+                    .iter()
+                    .filter(|entry| entry.contains_key("FILES"))
+                    .map(|entry| SyncFiles {
+                        repository: repo.name.clone(),
+                        name: entry
+                            .get("NAME")
+                            .and_then(|n| n.first())
+                            .cloned()
+                            .unwrap_or_default(),
+                        files: files_from_sections(entry),
+                    })
+            })
+            .collect();
+        FilesDb::new(packages)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures/snapshot.json");
+        let snapshot = Snapshot::read(&path).unwrap();
+
+        let installed = snapshot.installed();
+        assert_eq!(installed.len(), 2);
+        let glibc = installed.iter().find(|p| p.name == "glibc").unwrap();
+        assert_eq!(glibc.required_by, vec!["bash"]);
+        let bash = installed.iter().find(|p| p.name == "bash").unwrap();
+        assert_eq!(bash.dependencies[0].to_string(), "glibc>=2.38");
+        assert_eq!(snapshot.files(bash).unwrap()[2], "/usr/bin/bash");
+        assert_eq!(
+            snapshot.backups(),
+            vec![("bash".to_string(), vec!["/etc/bash.bashrc".to_string()])]
+        );
+
+        let sync = snapshot.sync();
+        assert_eq!(sync.len(), 3);
+        assert_eq!(sync[2].repository.as_deref(), Some("extra"));
+        assert!(snapshot.sync_files().files("zsh").is_some());
+        assert!(snapshot.sync_files().files("glibc").is_none());
+
+        assert!(Snapshot::parse("{\"local\": 1}").is_err());
+        assert_eq!(Snapshot::parse("{}").unwrap().installed(), vec![]);
+    }
+}
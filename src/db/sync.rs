@@ -0,0 +1,179 @@
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    io::{BufReader, Read},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    db::desc::{package_from_sections, parse_sections},
+    error::AppError,
+    structs::package::Package,
+};
+
+/// Open a repo database, which is a tar archive that may be gzip or zstd compressed
+pub fn open_archive(path: &Path) -> Result<tar::Archive<Box<dyn Read>>, AppError> {
+    let mut file = BufReader::new(File::open(path)?);
+    let mut magic = [0u8; 4];
+    let read = file.read(&mut magic)?;
+    //start again now that we know the compression
+    let file = BufReader::new(File::open(path)?);
+    let reader: Box<dyn Read> = match &magic[..read] {
+        [0x1f, 0x8b, ..] => Box::new(flate2::read::GzDecoder::new(file)),
+        [0x28, 0xb5, 0x2f, 0xfd] => Box::new(
+            ruzstd::decoding::StreamingDecoder::new(file)
+                .map_err(|e| AppError::Other(format!("{}: {e}", path.display())))?,
+        ),
+        _ => Box::new(file),
+    };
+    Ok(tar::Archive::new(reader))
+}
+
+/// Read the given files (e.g. `desc`) of every `<name>-<version>/` entry in a database archive.
+/// Files of the same entry are joined, so older databases with a separate `depends` file also work
+pub fn read_archive_entries(path: &Path, wanted: &[&str]) -> Result<Vec<String>, AppError> {
+    let mut entries: HashMap<String, String> = HashMap::new();
+    let mut archive = open_archive(path)?;
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let entry_path = entry.path()?.to_string_lossy().to_string();
+        let Some((dir, file)) = entry_path.trim_end_matches('/').split_once('/') else {
+            continue;
+        };
+        if !wanted.contains(&file) {
+            continue;
+        }
+        let mut content = String::new();
+        entry.read_to_string(&mut content)?;
+        let joined = entries.entry(dir.to_string()).or_default();
+        joined.push_str(&content);
+        joined.push('\n');
+    }
+    Ok(entries.into_values().collect())
+}
+
+/// Read all packages of one repository database, tagging them with the repository name
+pub fn read_sync_db(path: &Path, repo: &str) -> Result<Vec<Package>, AppError> {
+    let packs = read_archive_entries(path, &["desc", "depends"])?
+        .iter()
+        .map(|content| Package {
+            repository: Some(repo.to_string()),
+            ..package_from_sections(&parse_sections(content))
+        })
+        .filter(|p| !p.name.is_empty())
+        .collect();
+    Ok(packs)
+}
+
+/// Read `<db_path>/sync/<repo>.db` for every repository.
+/// If no repositories are given, all databases in the sync folder are read in name order
+pub fn read_sync_packages(db_path: &Path, repos: &[String]) -> Result<Vec<Package>, AppError> {
+    let mut packs = vec![];
+    for (repo, path) in sync_databases(db_path, repos, "db")? {
+        packs.extend(read_sync_db(&path, &repo)?);
+    }
+    Ok(packs)
+}
+
+/// The existing `<db_path>/sync/<repo>.<extension>` files, in repository order.
+/// Without repositories, every file with the extension is used in name order
+pub fn sync_databases(
+    db_path: &Path,
+    repos: &[String],
+    extension: &str,
+) -> Result<Vec<(String, PathBuf)>, AppError> {
+    let sync = db_path.join("sync");
+    let mut repos = repos.to_vec();
+    if repos.is_empty() {
+        for entry in fs::read_dir(&sync)? {
+            let name = entry?.file_name().to_string_lossy().to_string();
+            if let Some(repo) = name.strip_suffix(&format!(".{extension}")) {
+                repos.push(repo.to_string());
+            }
+        }
+        repos.sort();
+    }
+    Ok(repos
+        .into_iter()
+        .map(|repo| {
+            let path = sync.join(format!("{repo}.{extension}"));
+            (repo, path)
+        })
+        .filter(|(_, path)| path.exists())
+        .collect())
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Build a gzip compressed database from `(directory, desc)` pairs
+    pub(crate) fn write_db(path: &Path, entries: &[(&str, &str, &str)]) {
+        let file = File::create(path).unwrap();
+        let gz = flate2::write::GzEncoder::new(file, flate2::Compression::default());
+        let mut builder = tar::Builder::new(gz);
+        for (dir, name, content) in entries {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(content.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder
+                .append_data(&mut header, format!("{dir}/{name}"), content.as_bytes())
+                .unwrap();
+        }
+        builder.into_inner().unwrap().finish().unwrap();
+    }
+
+    pub(crate) fn temp_db_path(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("mspacman-{name}-{}", std::process::id()));
+        fs::create_dir_all(path.join("sync")).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_read_sync() {
+        let db = temp_db_path("sync");
+        write_db(
+            &db.join("sync/core.db"),
+            &[
+                (
+                    "bash-5.2.026-2",
+                    "desc",
+                    "%NAME%\nbash\n\n%VERSION%\n5.2.026-2\n\n%CSIZE%\n1800000\n\n%ISIZE%\n9453056\n\n%DEPENDS%\nreadline\nglibc>=2.38\n\n%PROVIDES%\nsh\n",
+                ),
+                (
+                    "glibc-2.40-1",
+                    "desc",
+                    "%NAME%\nglibc\n\n%VERSION%\n2.40-1\n",
+                ),
+                //old style database with separate depends file
+                ("glibc-2.40-1", "depends", "%DEPENDS%\ntzdata\n"),
+            ],
+        );
+        write_db(
+            &db.join("sync/custom.db"),
+            &[(
+                "mytool-1.0-1",
+                "desc",
+                "%NAME%\nmytool\n\n%VERSION%\n1.0-1\n",
+            )],
+        );
+
+        let packs = read_sync_packages(&db, &["core".to_string(), "custom".to_string()]).unwrap();
+        assert_eq!(packs.len(), 3);
+        let bash = packs.iter().find(|p| p.name == "bash").unwrap();
+        assert_eq!(bash.repository.as_deref(), Some("core"));
+        assert_eq!(bash.installed_size, 9453056);
+        assert_eq!(bash.download_size, 1800000);
+        assert_eq!(bash.dependencies[1].to_string(), "glibc>=2.38");
+        assert!(bash.installed.is_none());
+        let glibc = packs.iter().find(|p| p.name == "glibc").unwrap();
+        assert_eq!(glibc.dependencies[0].name, "tzdata");
+        let mytool = packs.iter().find(|p| p.name == "mytool").unwrap();
+        assert_eq!(mytool.repository.as_deref(), Some("custom"));
+
+        //without a repository list, all databases are read
+        assert_eq!(read_sync_packages(&db, &[]).unwrap().len(), 3);
+        fs::remove_dir_all(db).unwrap();
+    }
+}
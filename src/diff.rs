@@ -0,0 +1,262 @@
+/// Lines of context around each change, as `diff -u`
+const CONTEXT: usize = 3;
+///total line count above which we do not diff, the time grows with lines times changes
+const MAX_LINES: usize = 20_000;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Op {
+    Same(usize, usize),
+    Removed(usize),
+    Added(usize),
+}
+
+/// Unified diff of two texts, like `diff -u old new`.
+/// Returns no lines when they are the same
+pub fn unified_diff(old: &str, new: &str, old_name: &str, new_name: &str) -> Vec<String> {
+    let a: Vec<&str> = old.lines().collect();
+    let b: Vec<&str> = new.lines().collect();
+    if a == b {
+        return vec![];
+    }
+    let mut out = vec![format!("--- {old_name}"), format!("+++ {new_name}")];
+    if a.len() + b.len() > MAX_LINES {
+        out.push("Files are too large to compare".to_string());
+        return out;
+    }
+    let ops = edit_script(&a, &b);
+
+    //group changes that are close together into hunks
+    let changes: Vec<usize> = (0..ops.len())
+        .filter(|&i| !matches!(ops[i], Op::Same(..)))
+        .collect();
+    let mut i = 0;
+    while i < changes.len() {
+        let start = changes[i].saturating_sub(CONTEXT);
+        let mut end = changes[i];
+        while i < changes.len() && changes[i] <= end + 2 * CONTEXT {
+            end = changes[i];
+            i += 1;
+        }
+        let end = (end + CONTEXT + 1).min(ops.len());
+        let hunk = &ops[start..end];
+
+        //position of the hunk in each file, counting from 1
+        let (old_start, new_start) = ops[..start].iter().fold((0, 0), |(o, n), op| match op {
+            Op::Same(..) => (o + 1, n + 1),
+            Op::Removed(_) => (o + 1, n),
+            Op::Added(_) => (o, n + 1),
+        });
+        let old_len = hunk.iter().filter(|op| !matches!(op, Op::Added(_))).count();
+        let new_len = hunk
+            .iter()
+            .filter(|op| !matches!(op, Op::Removed(_)))
+            .count();
+        out.push(format!(
+            "@@ -{},{old_len} +{},{new_len} @@",
+            old_start + usize::from(old_len > 0),
+            new_start + usize::from(new_len > 0)
+        ));
+        for op in hunk {
+            out.push(match *op {
+                Op::Same(x, _) => format!(" {}", a[x]),
+                Op::Removed(x) => format!("-{}", a[x]),
+                Op::Added(y) => format!("+{}", b[y]),
+            });
+        }
+    }
+    out
+}
+
+/// Shortest edit script, with Myers' linear space divide and conquer
+fn edit_script(a: &[&str], b: &[&str]) -> Vec<Op> {
+    let mut ops = vec![];
+    diff_range(a, b, 0, a.len(), 0, b.len(), &mut ops);
+    //removals first, they read better before the additions replacing them
+    let mut start = 0;
+    while start < ops.len() {
+        let end = ops[start..]
+            .iter()
+            .position(|op| matches!(op, Op::Same(..)))
+            .map_or(ops.len(), |p| start + p);
+        ops[start..end].sort_by_key(|op| matches!(op, Op::Added(_)));
+        start = end + 1;
+    }
+    ops
+}
+
+///diff `a[a0..a1]` against `b[b0..b1]`, split at the middle snake until one side is empty
+fn diff_range(
+    a: &[&str],
+    b: &[&str],
+    mut a0: usize,
+    mut a1: usize,
+    mut b0: usize,
+    mut b1: usize,
+    ops: &mut Vec<Op>,
+) {
+    while a0 < a1 && b0 < b1 && a[a0] == b[b0] {
+        ops.push(Op::Same(a0, b0));
+        a0 += 1;
+        b0 += 1;
+    }
+    let mut suffix = 0;
+    while a0 < a1 && b0 < b1 && a[a1 - 1] == b[b1 - 1] {
+        a1 -= 1;
+        b1 -= 1;
+        suffix += 1;
+    }
+    if a0 == a1 {
+        ops.extend((b0..b1).map(Op::Added));
+    } else if b0 == b1 {
+        ops.extend((a0..a1).map(Op::Removed));
+    } else {
+        //both ends differ, so there are at least two edits and both halves are smaller
+        let (xs, ys, xe, ye) = middle_snake(&a[a0..a1], &b[b0..b1]);
+        diff_range(a, b, a0, a0 + xs, b0, b0 + ys, ops);
+        ops.extend((0..xe - xs).map(|i| Op::Same(a0 + xs + i, b0 + ys + i)));
+        diff_range(a, b, a0 + xe, a1, b0 + ye, b1, ops);
+    }
+    ops.extend((0..suffix).map(|i| Op::Same(a1 + i, b1 + i)));
+}
+
+///start and end of a run of equal lines in the middle of a shortest edit script,
+///searching forward from the start and backward from the end at the same time
+fn middle_snake(a: &[&str], b: &[&str]) -> (usize, usize, usize, usize) {
+    let (n, m) = (a.len() as isize, b.len() as isize);
+    let delta = n - m;
+    let max = (n + m + 1) / 2;
+    let offset = max + 1;
+    //furthest x reached on each diagonal k = x - y, the backward one counts from the end
+    let mut forward = vec![0isize; 2 * max as usize + 3];
+    let mut backward = vec![0isize; 2 * max as usize + 3];
+    let at = |k: isize| (k + offset) as usize;
+    for d in 0..=max {
+        for k in (-d..=d).step_by(2) {
+            let mut x = if k == -d || (k != d && forward[at(k - 1)] < forward[at(k + 1)]) {
+                forward[at(k + 1)]
+            } else {
+                forward[at(k - 1)] + 1
+            };
+            let (xs, ys) = (x, x - k);
+            while x < n && x - k < m && a[x as usize] == b[(x - k) as usize] {
+                x += 1;
+            }
+            forward[at(k)] = x;
+            let back = delta - k;
+            if delta % 2 != 0 && back.abs() < d && x + backward[at(back)] >= n {
+                return (xs as usize, ys as usize, x as usize, (x - k) as usize);
+            }
+        }
+        for k in (-d..=d).step_by(2) {
+            let mut x = if k == -d || (k != d && backward[at(k - 1)] < backward[at(k + 1)]) {
+                backward[at(k + 1)]
+            } else {
+                backward[at(k - 1)] + 1
+            };
+            let (xs, ys) = (x, x - k);
+            while x < n && x - k < m && a[(n - 1 - x) as usize] == b[(m - 1 - (x - k)) as usize] {
+                x += 1;
+            }
+            backward[at(k)] = x;
+            let front = delta - k;
+            if delta % 2 == 0 && front.abs() <= d && x + forward[at(front)] >= n {
+                let (xe, ye) = (n - xs, m - ys);
+                return (
+                    (n - x) as usize,
+                    (m - (x - k)) as usize,
+                    xe as usize,
+                    ye as usize,
+                );
+            }
+        }
+    }
+    unreachable!("a shortest edit script has at most n + m edits")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unified_diff() {
+        assert!(unified_diff("a\nb\n", "a\nb\n", "old", "new").is_empty());
+
+        let old = "1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n11\n12\n13\n14\n15\n16\n";
+        let new = "1\n2\n3\n4\nfive\n6\n7\n8\n9\n10\n11\n12\n13\n14\n15\n16\n17\n";
+        assert_eq!(
+            unified_diff(old, new, "a", "b"),
+            vec![
+                "--- a",
+                "+++ b",
+                "@@ -2,7 +2,7 @@",
+                " 2",
+                " 3",
+                " 4",
+                "-5",
+                "+five",
+                " 6",
+                " 7",
+                " 8",
+                "@@ -14,3 +14,4 @@",
+                " 14",
+                " 15",
+                " 16",
+                "+17",
+            ]
+        );
+
+        //everything added to an empty file
+        assert_eq!(
+            unified_diff("", "x\n", "a", "b"),
+            vec!["--- a", "+++ b", "@@ -0,0 +1,1 @@", "+x"]
+        );
+    }
+
+    #[test]
+    fn test_edit_script() {
+        //lines from a small alphabet, so there are many equal lines to match up
+        let mut seed = 7u32;
+        let mut lines = |len: usize| -> Vec<&str> {
+            (0..len)
+                .map(|_| {
+                    seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+                    ["a", "b", "c", "d"][(seed >> 16) as usize % 4]
+                })
+                .collect()
+        };
+        for (n, m) in [(1, 1), (5, 9), (30, 20), (64, 64), (100, 3)] {
+            let (a, b) = (lines(n), lines(m));
+            let ops = edit_script(&a, &b);
+            let old: Vec<&str> = ops
+                .iter()
+                .filter_map(|op| match *op {
+                    Op::Same(x, _) | Op::Removed(x) => Some(a[x]),
+                    Op::Added(_) => None,
+                })
+                .collect();
+            let new: Vec<&str> = ops
+                .iter()
+                .filter_map(|op| match *op {
+                    Op::Same(_, y) => Some(b[y]),
+                    Op::Added(y) => Some(b[y]),
+                    Op::Removed(_) => None,
+                })
+                .collect();
+            assert_eq!((old, new), (a.clone(), b.clone()));
+
+            //as short as the longest common subsequence allows
+            let mut lcs = vec![vec![0; m + 1]; n + 1];
+            for i in (0..n).rev() {
+                for j in (0..m).rev() {
+                    lcs[i][j] = if a[i] == b[j] {
+                        lcs[i + 1][j + 1] + 1
+                    } else {
+                        lcs[i + 1][j].max(lcs[i][j + 1])
+                    };
+                }
+            }
+            let same = ops.iter().filter(|op| matches!(op, Op::Same(..))).count();
+            assert_eq!(same, lcs[0][0], "{n}x{m}");
+        }
+    }
+}
@@ -4,6 +4,11 @@ use std::{error::Error, fmt::Display, string::FromUtf8Error};
 pub enum AppError {
     Command(std::io::Error),     //error running command
     CommandNonZero(Option<i32>), //exit code is not zero
+    CommandFailed {
+        command: String,
+        code: Option<i32>,
+        stderr: String,
+    }, //transaction failed, with what it printed to stderr
     String(std::string::FromUtf8Error),
     DateError(jiff::Error),
     Other(String),
@@ -15,6 +20,21 @@ impl Display for AppError {
         match self {
             AppError::Command(e) => write!(f, "Command Error: {}", e),
             AppError::CommandNonZero(e) => write!(f, "Command Error: {:?}", e),
+            AppError::CommandFailed {
+                command,
+                code,
+                stderr,
+            } => {
+                match code {
+                    Some(code) => write!(f, "{command} exited with code {code}")?,
+                    None => write!(f, "{command} was killed by a signal")?,
+                }
+                //the last line is usually the reason, the rest was shown in the terminal
+                match stderr.lines().rev().find(|l| !l.trim().is_empty()) {
+                    Some(line) => write!(f, ": {}", line.trim()),
+                    None => Ok(()),
+                }
+            }
             AppError::String(e) => write!(f, "String Conversion Error: {}", e),
             AppError::Other(e) => write!(f, "Error: {}", e),
             AppError::DateError(e) => write!(f, "DateError: {}", e),
@@ -0,0 +1,117 @@
+use std::{
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+        mpsc::{Receiver, Sender, channel},
+    },
+    time::Duration,
+};
+
+use ratatui::crossterm::event::{self, Event, KeyEventKind};
+
+use crate::{error::AppError, structs::event::AppEvent};
+
+/// How often the ui redraws without input, so timed messages expire and spinners move
+pub const TICK_RATE: Duration = Duration::from_millis(200);
+
+/// One channel for terminal input, ticks and anything running in the background.
+/// The main loop redraws after every event it receives
+#[derive(Debug)]
+pub struct EventLoop {
+    tx: Sender<AppEvent>,
+    rx: Receiver<AppEvent>,
+    paused: Arc<AtomicBool>,
+    ///held by the input thread while it polls the terminal
+    input_lock: Arc<Mutex<()>>,
+}
+
+impl Default for EventLoop {
+    fn default() -> Self {
+        let (tx, rx) = channel();
+        Self {
+            tx,
+            rx,
+            paused: Arc::new(AtomicBool::new(false)),
+            input_lock: Arc::new(Mutex::new(())),
+        }
+    }
+}
+
+impl EventLoop {
+    /// For background work to send events to the main loop
+    pub fn sender(&self) -> Sender<AppEvent> {
+        self.tx.clone()
+    }
+
+    /// Start reading the terminal on a thread, sending a tick whenever there is no input
+    pub fn start_input(&self, tick_rate: Duration) {
+        let tx = self.tx.clone();
+        let paused = self.paused.clone();
+        let input_lock = self.input_lock.clone();
+        std::thread::spawn(move || {
+            loop {
+                if paused.load(Ordering::SeqCst) {
+                    std::thread::sleep(tick_rate);
+                    continue;
+                }
+                let _guard = input_lock.lock().unwrap_or_else(|e| e.into_inner());
+                //paused while we waited for the lock
+                if paused.load(Ordering::SeqCst) {
+                    continue;
+                }
+                let ev = match event::poll(tick_rate) {
+                    Ok(true) => match event::read() {
+                        Ok(Event::Key(key)) if key.kind == KeyEventKind::Press => {
+                            AppEvent::Key(key)
+                        }
+                        Ok(Event::Resize(_, _)) => AppEvent::Resize,
+                        Ok(_) => continue,
+                        Err(_) => break,
+                    },
+                    Ok(false) => AppEvent::Tick,
+                    Err(_) => break,
+                };
+                if tx.send(ev).is_err() {
+                    break; //main loop is gone
+                }
+            }
+        });
+    }
+
+    /// Stop reading the terminal, so a command run outside the ui gets all the input.
+    /// Returns once the input thread is no longer polling
+    pub fn pause_input(&self) {
+        self.paused.store(true, Ordering::SeqCst);
+        drop(self.input_lock.lock().unwrap_or_else(|e| e.into_inner()));
+    }
+
+    pub fn resume_input(&self) {
+        self.paused.store(false, Ordering::SeqCst);
+    }
+
+    /// Wait for the next event
+    pub fn next(&self) -> Result<AppEvent, AppError> {
+        self.rx
+            .recv()
+            .map_err(|_| "Event channel closed".to_string().into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_background_events() {
+        let events = EventLoop::default();
+        let tx = events.sender();
+        std::thread::spawn(move || tx.send(AppEvent::Tick).unwrap());
+        assert!(matches!(events.next().unwrap(), AppEvent::Tick));
+
+        //pausing without an input thread returns straight away
+        events.pause_input();
+        assert!(events.paused.load(Ordering::SeqCst));
+        events.resume_input();
+        assert!(!events.paused.load(Ordering::SeqCst));
+    }
+}
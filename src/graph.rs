@@ -0,0 +1,699 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::structs::{dependency::Dependency, package::Package, reason::Reason};
+
+/// All packages indexed by name, with the hard dependency edges between installed packages
+/// resolved through provides. Built once per refresh and shared by the widgets
+#[derive(Debug, Clone, Default)]
+pub struct PackageGraph {
+    packages: Vec<Package>,
+    by_name: HashMap<String, usize>,
+    ///virtual name to the installed package providing it
+    providers: HashMap<String, usize>,
+    ///virtual name to every package providing it, installed or not
+    provided_by: HashMap<String, Vec<usize>>,
+    dependencies: Vec<Vec<usize>>,
+    required_by: Vec<Vec<usize>>,
+    ///dependencies no installed package satisfies
+    unresolved: Vec<Vec<String>>,
+}
+
+impl PackageGraph {
+    pub fn new(packages: Vec<Package>) -> Self {
+        let mut by_name = HashMap::new();
+        let mut providers = HashMap::new();
+        let mut provided_by: HashMap<String, Vec<usize>> = HashMap::new();
+        for (i, pack) in packages.iter().enumerate() {
+            by_name.entry(pack.name.clone()).or_insert(i);
+            for prov in &pack.provides {
+                provided_by.entry(prov.name.clone()).or_default().push(i);
+            }
+            if pack.installed.is_some() {
+                for prov in &pack.provides {
+                    providers.entry(prov.name.clone()).or_insert(i);
+                }
+            }
+        }
+        let mut graph = Self {
+            dependencies: vec![vec![]; packages.len()],
+            required_by: vec![vec![]; packages.len()],
+            unresolved: vec![vec![]; packages.len()],
+            packages,
+            by_name,
+            providers,
+            provided_by,
+        };
+        for i in 0..graph.packages.len() {
+            if graph.packages[i].installed.is_none() {
+                continue;
+            }
+            for dep in &graph.packages[i].dependencies {
+                match graph.resolve_index(&dep.name) {
+                    Some(d) if d == i || graph.dependencies[i].contains(&d) => {}
+                    Some(d) => {
+                        graph.dependencies[i].push(d);
+                        graph.required_by[d].push(i);
+                    }
+                    None => graph.unresolved[i].push(dep.name.clone()),
+                }
+            }
+        }
+        graph
+    }
+
+    pub fn packages(&self) -> &[Package] {
+        &self.packages
+    }
+
+    pub fn installed(&self) -> impl Iterator<Item = &Package> {
+        self.packages.iter().filter(|p| p.installed.is_some())
+    }
+
+    pub fn package(&self, index: usize) -> &Package {
+        &self.packages[index]
+    }
+
+    /// Names of installed foreign packages the AUR lookup found, the ones an AUR helper can
+    /// handle. Locally built packages are foreign too but left to pacman
+    pub fn aur_installed(&self) -> HashSet<String> {
+        self.installed()
+            .filter(|p| p.repository.is_none() && p.aur.is_some())
+            .map(|p| p.name.clone())
+            .collect()
+    }
+
+    /// Any package with this exact name, installed first
+    pub fn get(&self, name: &str) -> Option<&Package> {
+        self.by_name.get(name).map(|&i| &self.packages[i])
+    }
+
+    /// The installed package with this name, else the installed package providing it
+    pub fn resolve(&self, name: &str) -> Option<&Package> {
+        self.resolve_index(name).map(|i| &self.packages[i])
+    }
+
+    pub fn resolve_index(&self, name: &str) -> Option<usize> {
+        match self.by_name.get(name) {
+            Some(&i) if self.packages[i].installed.is_some() => Some(i),
+            _ => self.providers.get(name).copied(),
+        }
+    }
+
+    /// Packages satisfying `dep`, the one with its name first and then its providers,
+    /// each in the order of `packages`
+    pub fn satisfying<'a>(&'a self, dep: &'a Dependency) -> impl Iterator<Item = &'a Package> {
+        let named = self.by_name.get(&dep.name).into_iter();
+        let providers = self.provided_by.get(&dep.name).into_iter().flatten();
+        named
+            .chain(providers)
+            .map(|&i| &self.packages[i])
+            .filter(|p| p.satisfies(dep))
+    }
+
+    /// Installed packages `index` directly depends on
+    pub fn dependencies(&self, index: usize) -> &[usize] {
+        &self.dependencies[index]
+    }
+
+    /// Installed packages directly depending on `index`
+    pub fn required_by(&self, index: usize) -> &[usize] {
+        &self.required_by[index]
+    }
+
+    /// Set `dependencies_count` of installed packages to the number of packages they
+    /// need recursively, counting missing dependencies too
+    pub fn count_dependencies(&mut self) {
+        let mut seen = vec![usize::MAX; self.packages.len()];
+        for i in 0..self.packages.len() {
+            if self.packages[i].installed.is_none() {
+                continue;
+            }
+            let mut missing: HashSet<&str> = HashSet::new();
+            let mut count = 0;
+            let mut stack = vec![i];
+            seen[i] = i;
+            while let Some(current) = stack.pop() {
+                missing.extend(self.unresolved[current].iter().map(|s| s.as_str()));
+                for &dep in &self.dependencies[current] {
+                    if seen[dep] != i {
+                        seen[dep] = i;
+                        count += 1;
+                        stack.push(dep);
+                    }
+                }
+            }
+            let count = count + missing.len();
+            self.packages[i].dependencies_count = count;
+        }
+    }
+}
+
+///stop looking after this many paths, heavily shared libraries can have thousands
+pub const MAX_WHY_PATHS: usize = 200;
+
+/// Every chain of hard dependencies from an explicitly installed package down to `name`.
+/// Each path starts with the explicit package and ends with `name`.
+/// We stop at the first explicit package going up, as that is already a reason to be installed
+pub fn why_paths(name: &str, graph: &PackageGraph) -> Vec<Vec<String>> {
+    let mut paths = vec![];
+    let mut path = vec![name];
+    let mut on_path: HashSet<&str> = HashSet::from([name]);
+    walk_up(graph, &mut path, &mut on_path, &mut paths);
+    paths.sort();
+    paths
+}
+
+fn walk_up<'a>(
+    graph: &'a PackageGraph,
+    path: &mut Vec<&'a str>,
+    on_path: &mut HashSet<&'a str>,
+    paths: &mut Vec<Vec<String>>,
+) {
+    if paths.len() >= MAX_WHY_PATHS {
+        return;
+    }
+    let current = path[path.len() - 1];
+    let Some(pack) = graph.resolve(current) else {
+        return;
+    };
+    if pack.reason == Reason::Explicit {
+        paths.push(path.iter().rev().map(|p| p.to_string()).collect());
+        return;
+    }
+    for req in &pack.required_by {
+        //prevent loops
+        if !on_path.insert(req.as_str()) {
+            continue;
+        }
+        path.push(req.as_str());
+        walk_up(graph, path, on_path, paths);
+        path.pop();
+        on_path.remove(req.as_str());
+    }
+}
+
+/// Why a tree node is not expanded any further
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TreeMark {
+    None,
+    Seen,    //already expanded earlier in the tree
+    Cycle,   //already on the path from the root
+    Missing, //not installed
+}
+
+/// A node of a pactree-style dependency tree.
+/// `name` is the installed package to jump to, `label` is what the parent asked for
+#[derive(Debug, Clone, PartialEq)]
+pub struct TreeNode {
+    pub name: String,
+    pub label: String,
+    pub mark: TreeMark,
+    pub children: Vec<TreeNode>,
+}
+
+/// The recursive dependency tree of `root`, or the tree of packages requiring it if `reverse`.
+/// Like pactree, each package is only expanded the first time it appears
+pub fn dependency_tree(root: &str, graph: &PackageGraph, reverse: bool) -> TreeNode {
+    let mut seen = HashSet::new();
+    let mut on_path = HashSet::new();
+    tree_node(
+        root,
+        root.to_string(),
+        graph,
+        reverse,
+        &mut seen,
+        &mut on_path,
+    )
+}
+
+fn tree_node(
+    name: &str,
+    label: String,
+    graph: &PackageGraph,
+    reverse: bool,
+    seen: &mut HashSet<String>,
+    on_path: &mut HashSet<String>,
+) -> TreeNode {
+    let mut node = TreeNode {
+        name: name.to_string(),
+        label,
+        mark: TreeMark::None,
+        children: vec![],
+    };
+    let Some(pack) = graph.resolve(name) else {
+        node.mark = TreeMark::Missing;
+        return node;
+    };
+    if on_path.contains(name) {
+        node.mark = TreeMark::Cycle;
+        return node;
+    }
+    if !seen.insert(name.to_string()) {
+        node.mark = TreeMark::Seen;
+        return node;
+    }
+
+    let children: Vec<(String, String)> = if reverse {
+        pack.required_by
+            .iter()
+            .map(|r| (r.clone(), r.clone()))
+            .collect()
+    } else {
+        pack.dependencies
+            .iter()
+            .map(|dep| match graph.resolve(&dep.name) {
+                Some(provider) if provider.name != dep.name => (
+                    provider.name.clone(),
+                    format!("{dep} via {}", provider.name),
+                ),
+                _ => (dep.name.clone(), dep.to_string()),
+            })
+            .collect()
+    };
+    on_path.insert(name.to_string());
+    for (child, label) in children {
+        node.children
+            .push(tree_node(&child, label, graph, reverse, seen, on_path));
+    }
+    on_path.remove(name);
+    node
+}
+
+/// Which way to walk from the root packages
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum GraphDirection {
+    #[default]
+    Forward, //dependencies
+    Reverse, //required by
+    Both,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum GraphFormat {
+    #[default]
+    Dot,
+    Mermaid,
+}
+
+impl GraphFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            GraphFormat::Dot => "dot",
+            GraphFormat::Mermaid => "mmd",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GraphOptions {
+    pub direction: GraphDirection,
+    ///None for no limit
+    pub depth: Option<usize>,
+    pub optional: bool,
+    pub format: GraphFormat,
+}
+
+impl Default for GraphOptions {
+    fn default() -> Self {
+        Self {
+            direction: GraphDirection::Forward,
+            depth: None,
+            optional: true,
+            format: GraphFormat::Dot,
+        }
+    }
+}
+
+/// An edge from a package to one of its dependencies
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Edge {
+    pub from: String,
+    pub to: String,
+    pub optional: bool,
+}
+
+/// Walk the dependency graph from the roots, breadth first up to the depth limit.
+/// Edges always point from the dependent package to its dependency, also when walking in reverse
+pub fn subgraph(roots: &[String], graph: &PackageGraph, options: &GraphOptions) -> Vec<Edge> {
+    //virtual names are followed to the installed provider
+    let resolve = |name: &str| -> String {
+        graph
+            .resolve(name)
+            .map_or(name.to_string(), |p| p.name.clone())
+    };
+
+    let mut edges: HashSet<Edge> = HashSet::new();
+    let walk_forward = options.direction != GraphDirection::Reverse;
+    let walk_reverse = options.direction != GraphDirection::Forward;
+    for (forward, walk) in [(true, walk_forward), (false, walk_reverse)] {
+        if !walk {
+            continue;
+        }
+        let mut seen: HashSet<String> = roots.iter().cloned().collect();
+        let mut level: Vec<String> = roots.to_vec();
+        let mut depth = 0;
+        while !level.is_empty() && options.depth.is_none_or(|d| depth < d) {
+            let mut next = vec![];
+            for name in &level {
+                let Some(pack) = graph.resolve(name) else {
+                    continue;
+                };
+                let mut neighbours: Vec<(String, bool)> = if forward {
+                    let hard = pack.dependencies.iter().map(|d| (resolve(&d.name), false));
+                    let optional = pack
+                        .dependencies_optional
+                        .iter()
+                        .map(|d| (resolve(d), true));
+                    hard.chain(optional).collect()
+                } else {
+                    let hard = pack.required_by.iter().map(|r| (r.clone(), false));
+                    let optional = pack.required_by_optional.iter().map(|r| (r.clone(), true));
+                    hard.chain(optional).collect()
+                };
+                neighbours.retain(|(_, optional)| options.optional || !optional);
+                for (other, optional) in neighbours {
+                    let (from, to) = if forward {
+                        (name.clone(), other.clone())
+                    } else {
+                        (other.clone(), name.clone())
+                    };
+                    edges.insert(Edge { from, to, optional });
+                    if seen.insert(other.clone()) {
+                        next.push(other);
+                    }
+                }
+            }
+            level = next;
+            depth += 1;
+        }
+    }
+    let mut edges: Vec<Edge> = edges.into_iter().collect();
+    edges.sort();
+    edges
+}
+
+/// Graphviz output, optional edges are dashed and the roots are bold
+pub fn to_dot(roots: &[String], edges: &[Edge]) -> String {
+    let mut out = String::from("digraph dependencies {\n    rankdir=LR;\n    node [shape=box];\n");
+    for root in roots {
+        out.push_str(&format!("    \"{root}\" [style=bold];\n"));
+    }
+    for edge in edges {
+        let style = if edge.optional { " [style=dashed]" } else { "" };
+        out.push_str(&format!(
+            "    \"{}\" -> \"{}\"{style};\n",
+            edge.from, edge.to
+        ));
+    }
+    out.push_str("}\n");
+    out
+}
+
+/// Mermaid flowchart output, optional edges are dotted
+pub fn to_mermaid(roots: &[String], edges: &[Edge]) -> String {
+    //mermaid ids cannot contain most punctuation, so we number the nodes and label them
+    let mut ids: HashMap<String, usize> = HashMap::new();
+    let mut out = String::from("flowchart LR\n");
+    let mut node = |name: &str, out: &mut String| -> String {
+        let len = ids.len();
+        let id = *ids.entry(name.to_string()).or_insert_with(|| {
+            out.push_str(&format!("    n{len}[\"{name}\"]\n"));
+            len
+        });
+        format!("n{id}")
+    };
+    for root in roots {
+        let id = node(root, &mut out);
+        out.push_str(&format!("    style {id} stroke-width:3px\n"));
+    }
+    for edge in edges {
+        let from = node(&edge.from, &mut out);
+        let to = node(&edge.to, &mut out);
+        let arrow = if edge.optional { "-.->" } else { "-->" };
+        out.push_str(&format!("    {from} {arrow} {to}\n"));
+    }
+    out
+}
+
+/// Export the subgraph of the roots in the chosen format
+pub fn export_graph(roots: &[String], graph: &PackageGraph, options: &GraphOptions) -> String {
+    let edges = subgraph(roots, graph, options);
+    match options.format {
+        GraphFormat::Dot => to_dot(roots, &edges),
+        GraphFormat::Mermaid => to_mermaid(roots, &edges),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{db::local::read_local_packages, structs::dependency::Dependency};
+    use std::path::Path;
+
+    #[test]
+    fn test_why() {
+        let mut packs =
+            read_local_packages(&Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures")).unwrap();
+        let graph = PackageGraph::new(packs.clone());
+        //glibc is explicit
+        assert_eq!(why_paths("glibc", &graph), vec![vec!["glibc"]]);
+        //which needs sh from bash
+        assert_eq!(
+            why_paths("readline", &graph),
+            vec![vec!["which", "bash", "readline"]]
+        );
+
+        packs.iter_mut().find(|p| p.name == "which").unwrap().reason = Reason::Dependency;
+        let graph = PackageGraph::new(packs);
+        assert!(why_paths("readline", &graph).is_empty());
+    }
+
+    #[test]
+    fn test_dependency_tree() {
+        let mut packs =
+            read_local_packages(&Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures")).unwrap();
+        let graph = PackageGraph::new(packs.clone());
+        let names = |node: &TreeNode| {
+            node.children
+                .iter()
+                .map(|c| (c.name.clone(), c.mark))
+                .collect::<Vec<_>>()
+        };
+
+        let tree = dependency_tree("which", &graph, false);
+        //sh is resolved to bash
+        assert_eq!(tree.children[0].name, "bash");
+        assert_eq!(tree.children[0].label, "sh via bash");
+        let bash = &tree.children[0];
+        assert_eq!(
+            names(bash),
+            vec![
+                ("readline".to_string(), TreeMark::None),
+                ("glibc".to_string(), TreeMark::Seen),
+                ("ncurses".to_string(), TreeMark::Missing),
+            ]
+        );
+        //glibc was expanded below readline first
+        assert_eq!(bash.children[0].children[0].name, "glibc");
+        assert_eq!(bash.children[0].children[0].mark, TreeMark::None);
+
+        let tree = dependency_tree("glibc", &graph, true);
+        assert_eq!(
+            names(&tree),
+            vec![
+                ("bash".to_string(), TreeMark::None),
+                ("readline".to_string(), TreeMark::None),
+            ]
+        );
+        assert_eq!(
+            names(&tree.children[1]),
+            vec![("bash".to_string(), TreeMark::Seen)]
+        );
+
+        //glibc -> bash -> glibc
+        let glibc = packs.iter_mut().find(|p| p.name == "glibc").unwrap();
+        glibc.dependencies = vec!["bash".into()];
+        let graph = PackageGraph::new(packs);
+        let tree = dependency_tree("glibc", &graph, false);
+        assert_eq!(tree.children[0].children[1].name, "glibc");
+        assert_eq!(tree.children[0].children[1].mark, TreeMark::Cycle);
+    }
+
+    #[test]
+    fn test_subgraph() {
+        let packs =
+            read_local_packages(&Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures")).unwrap();
+        let graph = PackageGraph::new(packs.clone());
+        let edge = |from: &str, to: &str, optional: bool| Edge {
+            from: from.to_string(),
+            to: to.to_string(),
+            optional,
+        };
+        let roots = vec!["bash".to_string()];
+
+        let options = GraphOptions {
+            depth: Some(1),
+            ..Default::default()
+        };
+        assert_eq!(
+            subgraph(&roots, &graph, &options),
+            vec![
+                edge("bash", "bash-completion", true),
+                edge("bash", "glibc", false),
+                edge("bash", "ncurses", false),
+                edge("bash", "readline", false),
+            ]
+        );
+
+        let options = GraphOptions {
+            direction: GraphDirection::Reverse,
+            ..Default::default()
+        };
+        //which depends on sh, provided by bash
+        assert_eq!(
+            subgraph(&roots, &graph, &options),
+            vec![edge("which", "bash", false)]
+        );
+
+        let options = GraphOptions {
+            depth: Some(2),
+            optional: false,
+            ..Default::default()
+        };
+        let edges = subgraph(&roots, &graph, &options);
+        assert!(edges.contains(&edge("readline", "glibc", false)));
+        assert!(edges.contains(&edge("glibc", "tzdata", false)));
+        assert!(!edges.iter().any(|e| e.optional));
+    }
+
+    #[test]
+    fn test_export() {
+        let roots = vec!["a".to_string()];
+        let edges = vec![
+            Edge {
+                from: "a".to_string(),
+                to: "b".to_string(),
+                optional: false,
+            },
+            Edge {
+                from: "a".to_string(),
+                to: "c++".to_string(),
+                optional: true,
+            },
+        ];
+        assert_eq!(
+            to_dot(&roots, &edges),
+            "digraph dependencies {\n    rankdir=LR;\n    node [shape=box];\n    \"a\" [style=bold];\n    \"a\" -> \"b\";\n    \"a\" -> \"c++\" [style=dashed];\n}\n"
+        );
+        assert_eq!(
+            to_mermaid(&roots, &edges),
+            "flowchart LR\n    n0[\"a\"]\n    style n0 stroke-width:3px\n    n1[\"b\"]\n    n0 --> n1\n    n2[\"c++\"]\n    n0 -.-> n2\n"
+        );
+    }
+
+    /// `count` installed packages, each depending on a few earlier ones and on a virtual name
+    fn synthetic_packages(count: usize) -> Vec<Package> {
+        let mut seed: u64 = 42;
+        let mut next = |max: usize| {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (seed >> 33) as usize % max
+        };
+        (0..count)
+            .map(|i| {
+                let mut dependencies: Vec<Dependency> = (0..4.min(i))
+                    .map(|_| format!("pkg{}", next(i)).as_str().into())
+                    .collect();
+                if i > 0 {
+                    dependencies.push(format!("virtual{}", next(i)).as_str().into());
+                }
+                Package {
+                    name: format!("pkg{i}"),
+                    installed: Some("2024-01-01 00:00:00".to_string()),
+                    provides: vec![format!("virtual{i}").as_str().into()],
+                    dependencies,
+                    ..Default::default()
+                }
+            })
+            .collect()
+    }
+
+    /// How counting worked before the graph: a linear search for every edge
+    fn count_linear(packages: &[Package]) -> Vec<usize> {
+        fn walk(name: &str, packages: &[Package], seen: &mut HashSet<String>) {
+            let Some(pack) = packages.iter().find(|p| p.name == name) else {
+                return;
+            };
+            for dep in &pack.dependencies {
+                let dep = if packages.iter().any(|p| p.name == dep.name) {
+                    dep.name.clone()
+                } else {
+                    match packages
+                        .iter()
+                        .find(|p| p.provides.iter().any(|v| v.name == dep.name))
+                    {
+                        Some(provider) => provider.name.clone(),
+                        None => dep.name.clone(),
+                    }
+                };
+                if seen.insert(dep.clone()) {
+                    walk(&dep, packages, seen);
+                }
+            }
+        }
+        packages
+            .iter()
+            .map(|p| {
+                let mut seen = HashSet::new();
+                walk(&p.name, packages, &mut seen);
+                seen.remove(&p.name);
+                seen.len()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_graph_counts() {
+        //the linear walk is quadratic per package, so only compare on a sample
+        let sample = &synthetic_packages(2000)[..300];
+        let mut graph = PackageGraph::new(sample.to_vec());
+        graph.count_dependencies();
+        for pack in sample {
+            assert!(graph.get(&pack.name).is_some());
+        }
+        let counts: Vec<usize> = graph
+            .packages()
+            .iter()
+            .map(|p| p.dependencies_count)
+            .collect();
+        assert_eq!(counts, count_linear(sample));
+    }
+
+    ///`cargo test --release -- --ignored` to time counting on a large system
+    #[test]
+    #[ignore]
+    fn test_graph_benchmark() {
+        let packages = synthetic_packages(2000);
+
+        let start = std::time::Instant::now();
+        let linear = count_linear(&packages);
+        let linear_time = start.elapsed();
+
+        let start = std::time::Instant::now();
+        let mut graph = PackageGraph::new(packages);
+        graph.count_dependencies();
+        let graph_time = start.elapsed();
+
+        let counts: Vec<usize> = graph
+            .packages()
+            .iter()
+            .map(|p| p.dependencies_count)
+            .collect();
+        assert_eq!(counts, linear);
+        assert!(
+            graph_time < linear_time,
+            "graph {graph_time:?}, linear {linear_time:?}"
+        );
+    }
+}
@@ -0,0 +1,260 @@
+use std::{
+    fmt::Display,
+    fs::{self, File},
+    io,
+    os::unix::fs::PermissionsExt,
+    path::Path,
+    time::UNIX_EPOCH,
+};
+
+use sha2::{Digest, Sha256};
+
+use crate::db::mtree::{EntryType, MtreeEntry};
+
+/// How a file on disk differs from what the package installed, like `pacman -Qkk`
+#[derive(Debug, Clone, PartialEq)]
+pub enum FileProblem {
+    Missing,
+    ///e.g. a directory where a file was installed
+    Type,
+    Link {
+        expected: String,
+        actual: String,
+    },
+    Size {
+        expected: u64,
+        actual: u64,
+    },
+    Checksum,
+    Mode {
+        expected: u32,
+        actual: u32,
+    },
+    Modified,
+    Unreadable(String),
+}
+
+impl FileProblem {
+    ///content changed or gone, rather than just metadata
+    pub fn is_serious(&self) -> bool {
+        !matches!(self, FileProblem::Mode { .. } | FileProblem::Modified)
+    }
+}
+
+impl Display for FileProblem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FileProblem::Missing => write!(f, "missing"),
+            FileProblem::Type => write!(f, "type mismatch"),
+            FileProblem::Link { expected, actual } => {
+                write!(f, "symlink points to {actual}, expected {expected}")
+            }
+            FileProblem::Size { expected, actual } => {
+                write!(f, "size {actual}, expected {expected}")
+            }
+            FileProblem::Checksum => write!(f, "checksum mismatch"),
+            FileProblem::Mode { expected, actual } => {
+                write!(f, "mode {actual:o}, expected {expected:o}")
+            }
+            FileProblem::Modified => write!(f, "modification time mismatch"),
+            FileProblem::Unreadable(e) => write!(f, "unreadable: {e}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileIssue {
+    pub path: String,
+    pub problem: FileProblem,
+}
+
+/// Compare each entry against the files under `root`. Every difference is reported,
+/// so a file can have more than one issue.
+/// Like pacman, `backup` files are expected to be edited, so only their type and mode are checked
+pub fn check_files(root: &Path, entries: &[MtreeEntry], backup: &[String]) -> Vec<FileIssue> {
+    let mut issues = vec![];
+    for entry in entries {
+        let path = root.join(entry.path.trim_start_matches('/'));
+        let mut issue = |problem| {
+            issues.push(FileIssue {
+                path: entry.path.clone(),
+                problem,
+            })
+        };
+        let meta = match fs::symlink_metadata(&path) {
+            Ok(meta) => meta,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                issue(FileProblem::Missing);
+                continue;
+            }
+            Err(e) => {
+                issue(FileProblem::Unreadable(e.to_string()));
+                continue;
+            }
+        };
+        let kind_matches = match entry.kind {
+            EntryType::File => meta.is_file(),
+            EntryType::Dir => meta.is_dir(),
+            EntryType::Link => meta.is_symlink(),
+        };
+        if !kind_matches {
+            issue(FileProblem::Type);
+            continue;
+        }
+        if entry.kind == EntryType::Link {
+            //mode and time of a symlink are not meaningful
+            let actual = fs::read_link(&path)
+                .map(|p| p.to_string_lossy().to_string())
+                .unwrap_or_default();
+            if let Some(expected) = &entry.link
+                && *expected != actual
+            {
+                issue(FileProblem::Link {
+                    expected: expected.clone(),
+                    actual,
+                });
+            }
+            continue;
+        }
+        if let Some(expected) = entry.mode {
+            let actual = meta.permissions().mode() & 0o7777;
+            if actual != expected {
+                issue(FileProblem::Mode { expected, actual });
+            }
+        }
+        if entry.kind == EntryType::Dir || backup.contains(&entry.path) {
+            continue; //directory times change whenever their content does
+        }
+        if let Some(expected) = entry.time {
+            let actual = meta
+                .modified()
+                .ok()
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .map(|d| d.as_secs() as i64);
+            if actual != Some(expected) {
+                issue(FileProblem::Modified);
+            }
+        }
+        if let Some(expected) = entry.size
+            && meta.len() != expected
+        {
+            issue(FileProblem::Size {
+                expected,
+                actual: meta.len(),
+            });
+        }
+        if let Some(expected) = &entry.sha256 {
+            match sha256_file(&path) {
+                Ok(actual) if actual != *expected => issue(FileProblem::Checksum),
+                Ok(_) => {}
+                Err(e) => issue(FileProblem::Unreadable(e.to_string())),
+            }
+        }
+    }
+    issues
+}
+
+fn sha256_file(path: &Path) -> Result<String, io::Error> {
+    let mut hasher = Sha256::new();
+    io::copy(&mut File::open(path)?, &mut hasher)?;
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, SystemTime};
+
+    #[test]
+    fn test_check_files() {
+        let root = std::env::temp_dir().join(format!("ms-integrity-{}", std::process::id()));
+        fs::create_dir_all(root.join("usr/bin")).unwrap();
+        let time = 1707334010;
+        let write = |name: &str, content: &str, mode: u32| {
+            let path = root.join(name);
+            fs::write(&path, content).unwrap();
+            fs::set_permissions(&path, fs::Permissions::from_mode(mode)).unwrap();
+            File::options()
+                .write(true)
+                .open(&path)
+                .unwrap()
+                .set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(time as u64))
+                .unwrap();
+        };
+        write("usr/bin/good", "hello", 0o755);
+        write("usr/bin/edited", "hellO", 0o755);
+        write("usr/bin/chmod", "hello", 0o700);
+        write("usr/bin/config", "edited by the user", 0o644);
+        std::os::unix::fs::symlink("good", root.join("usr/bin/link")).unwrap();
+
+        //sha256 of "hello"
+        let sha = "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824";
+        let file = |path: &str| MtreeEntry {
+            path: path.to_string(),
+            kind: EntryType::File,
+            mode: Some(0o755),
+            size: Some(5),
+            time: Some(time),
+            sha256: Some(sha.to_string()),
+            link: None,
+        };
+        let entries = vec![
+            MtreeEntry {
+                kind: EntryType::Dir,
+                size: None,
+                sha256: None,
+                ..file("/usr/bin")
+            },
+            file("/usr/bin/good"),
+            file("/usr/bin/edited"),
+            file("/usr/bin/chmod"),
+            file("/usr/bin/gone"),
+            file("/usr/bin/config"),
+            MtreeEntry {
+                kind: EntryType::Link,
+                link: Some("other".to_string()),
+                ..file("/usr/bin/link")
+            },
+        ];
+        let issues = check_files(&root, &entries, &["/usr/bin/config".to_string()]);
+        let issue = |path: &str, problem| FileIssue {
+            path: path.to_string(),
+            problem,
+        };
+        assert_eq!(
+            issues,
+            vec![
+                issue("/usr/bin/edited", FileProblem::Checksum),
+                issue(
+                    "/usr/bin/chmod",
+                    FileProblem::Mode {
+                        expected: 0o755,
+                        actual: 0o700
+                    }
+                ),
+                issue("/usr/bin/gone", FileProblem::Missing),
+                //a backup file only has its mode checked
+                issue(
+                    "/usr/bin/config",
+                    FileProblem::Mode {
+                        expected: 0o755,
+                        actual: 0o644
+                    }
+                ),
+                issue(
+                    "/usr/bin/link",
+                    FileProblem::Link {
+                        expected: "other".to_string(),
+                        actual: "good".to_string()
+                    }
+                ),
+            ]
+        );
+        assert!(!issues[1].problem.is_serious());
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
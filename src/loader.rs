@@ -0,0 +1,224 @@
+use std::{
+    fmt::Display,
+    path::PathBuf,
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+        mpsc::Sender,
+    },
+    time::Instant,
+};
+
+use crate::{
+    cache::CachedPackage,
+    db::{files::FilesDb, log::Transaction},
+    error::AppError,
+    graph::PackageGraph,
+    integrity::FileIssue,
+    owner::OwnerScan,
+    pacnew::PacfileScan,
+    pman::load_packages,
+    source::PackageSource,
+    structs::event::AppEvent,
+};
+
+static NEXT_LOADER: AtomicUsize = AtomicUsize::new(0);
+
+const SPINNER: [char; 10] = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+
+/// The parts of a refresh, reported as each one finishes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LoadStage {
+    Installed,
+    Sync,
+    Updates,
+    Sizes,
+    Aur,
+}
+
+impl LoadStage {
+    pub fn values() -> [LoadStage; 5] {
+        [
+            LoadStage::Installed,
+            LoadStage::Sync,
+            LoadStage::Updates,
+            LoadStage::Sizes,
+            LoadStage::Aur,
+        ]
+    }
+}
+
+impl Display for LoadStage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            LoadStage::Installed => "installed",
+            LoadStage::Sync => "sync",
+            LoadStage::Updates => "updates",
+            LoadStage::Sizes => "sizes",
+            LoadStage::Aur => "aur",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// Sent by background threads, package loads are tagged with the loader they belong to
+#[derive(Debug)]
+pub enum LoadEvent {
+    Progress(usize, LoadStage),
+    ///something was left out, the load goes on
+    Warning(usize, String),
+    ///boxed, the graph is much larger than the other events
+    Done(usize, Result<Box<PackageGraph>, AppError>),
+    FilesDb(Result<FilesDb, AppError>),
+    ///issues of each checked package
+    FileIssues(Vec<(String, Result<Vec<FileIssue>, AppError>)>),
+    ///owners of the searched paths
+    Owners(Vec<PathBuf>, OwnerScan),
+    Pacfiles(Result<PacfileScan, AppError>),
+    History(Result<Vec<Transaction>, AppError>),
+    Cache(Result<Vec<CachedPackage>, AppError>),
+}
+
+/// Run `job` on its own thread, its result arrives as `AppEvent::Load` like a package load
+pub fn spawn_job(tx: Sender<AppEvent>, job: impl FnOnce() -> LoadEvent + Send + 'static) {
+    std::thread::spawn(move || {
+        let _ = tx.send(AppEvent::Load(job()));
+    });
+}
+
+/// Loads the packages on a background thread, so the ui keeps running on the old data.
+/// Progress and the result arrive as `AppEvent::Load` on the app's event channel
+#[derive(Debug)]
+pub struct Loader {
+    id: usize,
+    done: Vec<LoadStage>,
+    warnings: Vec<String>,
+    started: Instant,
+}
+
+impl Loader {
+    pub fn spawn(source: Arc<dyn PackageSource>, tx: Sender<AppEvent>) -> Self {
+        let id = NEXT_LOADER.fetch_add(1, Ordering::Relaxed);
+        std::thread::spawn(move || {
+            let progress = tx.clone();
+            let res = load_packages(
+                source.as_ref(),
+                &|stage| {
+                    let _ = progress.send(AppEvent::Load(LoadEvent::Progress(id, stage)));
+                },
+                &|warning| {
+                    let _ = tx.send(AppEvent::Load(LoadEvent::Warning(id, warning)));
+                },
+            );
+            //the receiver is gone if the app quit while loading
+            let _ = tx.send(AppEvent::Load(LoadEvent::Done(id, res.map(Box::new))));
+        });
+        Self {
+            id,
+            done: vec![],
+            warnings: vec![],
+            started: Instant::now(),
+        }
+    }
+
+    /// Record progress, returns the result once finished.
+    /// Events from an older loader that was replaced are ignored
+    pub fn handle(&mut self, event: LoadEvent) -> Option<Result<PackageGraph, AppError>> {
+        match event {
+            LoadEvent::Progress(id, stage) if id == self.id => self.done.push(stage),
+            LoadEvent::Warning(id, warning) if id == self.id => self.warnings.push(warning),
+            LoadEvent::Done(id, res) if id == self.id => return Some(res.map(|g| *g)),
+            _ => {}
+        }
+        None
+    }
+
+    /// Problems reported so far that did not stop the load
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
+    }
+
+    /// Spinner followed by each stage, ticked once finished
+    pub fn status(&self) -> String {
+        let frame = (self.started.elapsed().as_millis() / 100) as usize % SPINNER.len();
+        let stages = LoadStage::values()
+            .iter()
+            .map(|s| {
+                let mark = if self.done.contains(s) { "✓" } else { "…" };
+                format!("{s} {mark}")
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+        format!("{} Loading: {stages}", SPINNER[frame])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{eventloop::EventLoop, source::fixture::FixtureSource};
+    use std::path::Path;
+
+    #[test]
+    fn test_loader() {
+        let source: Arc<dyn PackageSource> = Arc::new(FixtureSource::new(
+            &Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures"),
+        ));
+        let events = EventLoop::default();
+        let old = Loader::spawn(source.clone(), events.sender());
+        let mut loader = Loader::spawn(source, events.sender());
+        assert_ne!(old.id, loader.id);
+        assert!(loader.status().contains("installed …"));
+
+        //both finish, only the newest is used
+        let mut finished = 0;
+        let mut graph = None;
+        while finished < 2 {
+            let AppEvent::Load(ev) = events.next().unwrap() else {
+                panic!("unexpected event");
+            };
+            if matches!(ev, LoadEvent::Done(..)) {
+                finished += 1;
+            }
+            if let Some(res) = loader.handle(ev) {
+                assert!(graph.is_none());
+                graph = Some(res.unwrap());
+            }
+        }
+        let graph = graph.unwrap();
+        assert_eq!(graph.packages().len(), 7);
+        for stage in LoadStage::values() {
+            assert!(loader.done.contains(&stage), "{stage} not reported");
+        }
+        assert!(loader.status().contains("aur ✓"));
+    }
+
+    #[test]
+    fn test_loader_warning() {
+        //an unreadable AUR answer still loads the packages, with a warning
+        let dir = std::env::temp_dir().join(format!("ms-loader-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::copy(
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures/snapshot.json"),
+            dir.join("snapshot.json"),
+        )
+        .unwrap();
+        std::fs::write(dir.join("aur.json"), "not json").unwrap();
+        let source = FixtureSource::from_snapshot(&dir.join("snapshot.json")).unwrap();
+
+        let events = EventLoop::default();
+        let mut loader = Loader::spawn(Arc::new(source), events.sender());
+        let res = loop {
+            let AppEvent::Load(ev) = events.next().unwrap() else {
+                panic!("unexpected event");
+            };
+            if let Some(res) = loader.handle(ev) {
+                break res;
+            }
+        };
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(res.unwrap().installed().count(), 2);
+        assert_eq!(loader.warnings().len(), 1);
+        assert!(loader.warnings()[0].starts_with("AUR lookup failed"));
+    }
+}
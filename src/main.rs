@@ -1,5 +1,19 @@
+pub mod aur;
+mod cache;
+pub mod cli;
+pub mod config;
+pub mod db;
+mod diff;
 pub mod error;
+pub mod eventloop;
+pub mod graph;
+mod integrity;
+pub mod loader;
+pub mod owner;
+mod pacnew;
+pub mod plan;
 pub mod pman;
+pub mod source;
 pub mod structs;
 pub mod utils;
 pub mod version;
@@ -7,7 +21,7 @@ pub mod widgets;
 
 use ratatui::{
     DefaultTerminal, Frame,
-    crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
+    crossterm::event::{KeyCode, KeyEventKind, KeyModifiers},
     layout::{Alignment, Constraint, Layout, Rect},
     style::{Color, Style},
     text::Text,
@@ -15,37 +29,83 @@ use ratatui::{
 };
 use std::{
     error::Error,
+    sync::Arc,
     time::{Duration, Instant},
 };
 
 use crate::{
+    cli::{Args, USAGE, run_cli},
+    config::{Config, config_path},
+    db::log::Timeline,
     error::AppError,
-    pman::{pacman_exists, refresh_packages_and_update_tables, run_command},
+    eventloop::TICK_RATE,
+    pman::{handle_load_event, pacman_exists, run_command, start_job, start_refresh},
+    source::{PackageSource, fixture::FixtureSource, pacman::PacmanSource},
     structs::{
         appstate::AppState,
-        event::{EventCommand, EventResult},
+        event::{AppEvent, BackgroundJob, EventCommand, EventResult},
         package::Package,
         tab::Tab,
         timedstring::TimedString,
     },
+    utils::{age, thousands},
     widgets::{Commands, CurrentPackage},
 };
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    println!("Collecting packages...");
-    if !pacman_exists() {
-        println!("pacman is not installed");
-        std::process::exit(1);
-    }
-
-    let mut state = AppState::default();
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let args = match Args::parse(&args) {
+        Ok(args) => args,
+        Err(e) => {
+            println!("{e}\n{USAGE}");
+            std::process::exit(1);
+        }
+    };
+    let config = match args.config.clone().or_else(config_path) {
+        Some(path) => Config::read(&path).unwrap_or_else(|e| {
+            println!("{e}");
+            std::process::exit(1);
+        }),
+        None => Config::default(),
+    };
+    let source: Arc<dyn PackageSource> = match &args.fixture {
+        Some(path) => {
+            let fixture = match path.is_file() {
+                true => FixtureSource::from_snapshot(path),
+                false => Ok(FixtureSource::new(path)),
+            };
+            match fixture {
+                Ok(fixture) => Arc::new(fixture.with_config(&config)),
+                Err(e) => {
+                    println!("{e}");
+                    std::process::exit(1);
+                }
+            }
+        }
+        None => {
+            if !pacman_exists() {
+                println!("pacman is not installed");
+                std::process::exit(1);
+            }
+            Arc::new(PacmanSource::new(&config))
+        }
+    };
 
-    let res = refresh_packages_and_update_tables(&mut state);
-    if let Err(e) = res {
-        eprintln!("Error getting package list: {e}");
-        std::process::exit(1);
+    if let Some(command) = &args.command {
+        match run_cli(command, source.as_ref()) {
+            Ok(out) => print!("{out}"),
+            Err(e) => {
+                eprintln!("{e}");
+                std::process::exit(1);
+            }
+        }
+        return Ok(());
     }
 
+    let mut state = AppState::new(source);
+    start_refresh(&mut state);
+    state.events.start_input(TICK_RATE);
+
     let mut terminal = ratatui::init();
     terminal.clear()?;
     let appresult = run(&mut terminal, state);
@@ -62,7 +122,7 @@ fn run(terminal: &mut DefaultTerminal, mut state: AppState) -> Result<(), AppErr
     loop {
         terminal.draw(|f| {
             let _start = Instant::now();
-            let info = if state.show_info { 5 } else { 0 };
+            let info = if state.show_info { 7 } else { 0 };
 
             use Constraint::{Length, Min};
             let vertical = Layout::vertical([Length(3), Min(0), Length(info), Length(1)]);
@@ -73,6 +133,18 @@ fn run(terminal: &mut DefaultTerminal, mut state: AppState) -> Result<(), AppErr
             match state.tab {
                 Tab::Installed | Tab::Packages => draw_packages(&mut state, f, inner_area),
                 Tab::Updates => draw_updates(&mut state, f, inner_area),
+                Tab::Pacnew => {
+                    let widget = state.pacnew_widget.clone();
+                    widget.render(inner_area, f.buffer_mut());
+                }
+                Tab::History => {
+                    let widget = state.history_widget.clone();
+                    widget.render(inner_area, f.buffer_mut());
+                }
+                Tab::Cache => {
+                    let widget = state.cache_widget.clone();
+                    widget.render(inner_area, f.buffer_mut());
+                }
             }
             draw_info(&mut state, f, info_area).unwrap();
             draw_status(&mut state, f, footer_area).unwrap();
@@ -89,9 +161,11 @@ fn run(terminal: &mut DefaultTerminal, mut state: AppState) -> Result<(), AppErr
             EventResult::Quit => return Ok(()),
             EventResult::Command(c) => {
                 state.message = TimedString::new("Running command...", Duration::from_secs(10));
+                state.events.pause_input();
                 let _ = goto_screen(false, terminal);
                 let res = run_command(&mut state, c);
                 let _ = goto_screen(true, terminal);
+                state.events.resume_input();
                 if let Err(e) = res {
                     state.message =
                         TimedString::new(e.to_string().as_str(), Duration::from_secs(10));
@@ -99,13 +173,19 @@ fn run(terminal: &mut DefaultTerminal, mut state: AppState) -> Result<(), AppErr
                     state.message = TimedString::new("Command completed.", Duration::from_secs(10));
                 }
             }
-            EventResult::NeedsUpdate => {
-                refresh_packages_and_update_tables(&mut state)?;
-            }
+            EventResult::NeedsUpdate => start_refresh(&mut state),
+            EventResult::Background(job) => start_job(&state, job),
             EventResult::GotoInstalled(name) => {
                 state.tab = Tab::Installed;
                 state.installed_widget.goto_package_by_name(&name);
             }
+            EventResult::GotoPackage(name) => {
+                state.tab = Tab::Packages;
+                state.packages_widget.goto_package_by_name(&name);
+            }
+            EventResult::Message(message) => {
+                state.message = TimedString::new(&message, Duration::from_secs(10));
+            }
         }
     }
 }
@@ -147,63 +227,91 @@ fn draw_packages(state: &mut AppState, f: &mut Frame<'_>, area: Rect) {
 }
 
 fn handle_event(state: &mut AppState) -> Result<EventResult, AppError> {
-    if let Event::Key(key) = event::read()? {
-        //priority is ctrl+c
-        match key.code {
-            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                return Ok(EventResult::Quit);
+    let key = match state.events.next()? {
+        AppEvent::Key(key) => key,
+        AppEvent::Load(ev) => {
+            if let Err(e) = handle_load_event(state, ev) {
+                state.message = TimedString::new(
+                    &format!("Error getting package list: {e}"),
+                    Duration::from_secs(10),
+                );
             }
-            _ => {}
+            return Ok(EventResult::None);
         }
+        //nothing to do but redraw, timed messages and the spinner update themselves
+        AppEvent::Tick | AppEvent::Resize => return Ok(EventResult::None),
+    };
+    //priority is ctrl+c
+    match key.code {
+        KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            return Ok(EventResult::Quit);
+        }
+        _ => {}
+    }
 
-        //if showing help
-        if state.show_help {
-            match key.code {
-                KeyCode::Char('?') | KeyCode::Esc => {
-                    state.show_help = false;
-                }
-                _ => {}
+    //if showing help
+    if state.show_help {
+        match key.code {
+            KeyCode::Char('?') | KeyCode::Esc => {
+                state.show_help = false;
             }
-            //no other actions allowed
-            return Ok(EventResult::None);
+            _ => {}
         }
+        //no other actions allowed
+        return Ok(EventResult::None);
+    }
 
-        //next we handle based on focus
-        let res = match state.tab {
-            Tab::Installed => state.installed_widget.handle_key_event(&key),
-            Tab::Packages => state.packages_widget.handle_key_event(&key),
-            Tab::Updates => state.update_widget.handle_key_event(&key),
-        };
-        if let Some(res) = res {
-            return Ok(res);
-        }
+    //next we handle based on focus
+    let res = match state.tab {
+        Tab::Installed => state.installed_widget.handle_key_event(&key),
+        Tab::Packages => state.packages_widget.handle_key_event(&key),
+        Tab::Updates => state.update_widget.handle_key_event(&key),
+        Tab::Pacnew => state.pacnew_widget.handle_key_event(&key),
+        Tab::History => state.history_widget.handle_key_event(&key),
+        Tab::Cache => state.cache_widget.handle_key_event(&key),
+    };
+    if let Some(res) = res {
+        return Ok(res);
+    }
 
-        //final global key handling
-        if key.kind == KeyEventKind::Press {
-            match key.code {
-                KeyCode::Char('?') => state.show_help = true,
-                KeyCode::Char('q') => return Ok(EventResult::Quit),
-                KeyCode::Tab => {
-                    state.tab.cycle_next();
-                    return Ok(EventResult::None);
-                }
-                KeyCode::BackTab => {
-                    state.tab.cycle_prev();
-                    return Ok(EventResult::None);
-                }
-                KeyCode::Char('s') => {
-                    return Ok(EventResult::Command(EventCommand::SyncDatabase));
-                }
+    //final global key handling
+    if key.kind == KeyEventKind::Press {
+        match key.code {
+            KeyCode::Char('?') => state.show_help = true,
+            KeyCode::Char('q') => return Ok(EventResult::Quit),
+            KeyCode::Tab => {
+                state.tab.cycle_next();
+                tab_opened(state);
+                return Ok(EventResult::None);
+            }
+            KeyCode::BackTab => {
+                state.tab.cycle_prev();
+                tab_opened(state);
+                return Ok(EventResult::None);
+            }
+            KeyCode::Char('s') => {
+                return Ok(EventResult::Command(EventCommand::SyncDatabase));
+            }
 
-                KeyCode::Char('i') => state.show_info = !state.show_info,
+            KeyCode::Char('i') => state.show_info = !state.show_info,
 
-                _ => {}
-            }
+            _ => {}
         }
     }
     Ok(EventResult::None)
 }
 
+/// Tabs that are not part of the package list load their data in the background when opened
+fn tab_opened(state: &mut AppState) {
+    let job = match state.tab {
+        Tab::Pacnew => state.pacnew_widget.refresh(),
+        Tab::History => state.history_widget.refresh(),
+        Tab::Cache => state.cache_widget.refresh(),
+        _ => return,
+    };
+    start_job(state, job);
+}
+
 fn goto_screen(alternate: bool, terminal: &mut DefaultTerminal) -> Result<(), Box<dyn Error>> {
     use ratatui::crossterm::ExecutableCommand;
     use ratatui::crossterm::terminal::EnterAlternateScreen;
@@ -224,21 +332,28 @@ fn goto_screen(alternate: bool, terminal: &mut DefaultTerminal) -> Result<(), Bo
 
 fn update_tables(state: &mut AppState) {
     //installed
-    let packs: Vec<_> = state
-        .packages
-        .iter()
-        .filter(|p| p.installed.is_some())
-        .cloned()
-        .collect();
-    state.installed_widget.set_data(packs);
+    state.installed_widget.set_data(state.packages.clone());
 
     //all packages
-    state.packages_widget.set_data(&state.packages);
+    state.packages_widget.set_data(state.packages.clone());
+
+    //pacnew files, history and cache, only read once their tab or the info panel needed them
+    state.history = None;
+    state.history_loading = false;
+    let jobs = [
+        state.pacnew_widget.set_data(state.packages.clone()),
+        state.history_widget.set_data(state.packages.clone()),
+        state.cache_widget.set_data(state.packages.clone()),
+    ];
+    for job in jobs.into_iter().flatten() {
+        start_job(state, job);
+    }
 
     //updates
     state.update_widget.set_data(
         &state
             .packages
+            .packages()
             .iter()
             .filter(|a| a.new_version.is_some())
             .cloned()
@@ -247,24 +362,107 @@ fn update_tables(state: &mut AppState) {
 }
 
 fn draw_info(state: &mut AppState, f: &mut Frame, rect: Rect) -> Result<(), Box<dyn Error>> {
+    if state.show_info
+        && state.history.is_none()
+        && !state.history_loading
+        && current_pack(state).is_some()
+    {
+        state.history_loading = true;
+        start_job(state, BackgroundJob::History);
+    }
     //info
     let pack = current_pack(state);
     if pack.is_none() {
         return Ok(());
     }
     let pack = pack.unwrap();
-    let rows: Vec<Row> = [
+    let size = thousands(pack.installed_size);
+    let licenses = pack.licenses.join(" ");
+    let groups = pack.groups.join(" ");
+    let repository = match &pack.aur {
+        Some(aur) => format!("aur {}, {}", aur.version, aur.summary()),
+        None => pack.repository.clone().unwrap_or_default(),
+    };
+    let left: Vec<Row> = [
         ("Name", pack.name.as_str()),
         ("Version", pack.version.as_str()),
         ("Description", pack.description.as_str()),
+        ("Repository", repository.as_str()),
+        ("Groups", groups.as_str()),
     ]
     .iter()
     .map(|s| Row::from_iter([s.0, s.1]))
     .collect();
-    let table = Table::new(rows, [Constraint::Length(15), Constraint::Min(0)])
-        .block(Block::bordered().title("Info"));
+    let right: Vec<Row> = [
+        ("Installed Size", size.as_str()),
+        ("URL", pack.url.as_str()),
+        ("Licenses", licenses.as_str()),
+        ("Packager", pack.packager.as_str()),
+    ]
+    .iter()
+    .map(|s| Row::from_iter([s.0, s.1]))
+    .collect();
+    let widths = [Constraint::Length(15), Constraint::Min(0)];
+    let left = Table::new(left, widths).block(Block::bordered().title("Info"));
+    let right = Table::new(right, widths).block(Block::bordered());
+
+    //versions from the log, newest first
+    let timeline = Timeline::new(state.history.as_deref().unwrap_or_default(), &pack.name);
+    let mut history: Vec<Row> = timeline
+        .entries
+        .iter()
+        .rev()
+        .map(|e| {
+            let version = e.new_version.as_deref().or(e.old_version.as_deref());
+            Row::from_iter([
+                e.date.get(..10).unwrap_or(&e.date).to_string(),
+                e.action.to_string(),
+                version.unwrap_or_default().to_string(),
+            ])
+        })
+        .collect();
+    if state.history.is_none() {
+        history.push(Row::from_iter(["Reading pacman.log…"]));
+    }
+    //the install date of the current version is also in the local db, for trimmed logs
+    let current_since = match timeline.current_since() {
+        Some(date) => Some(date),
+        None if timeline.entries.is_empty() => pack.installed.as_deref(),
+        None => None,
+    };
+    let mut title = vec![];
+    if let Some(first) = timeline.first_installed() {
+        title.push(format!("since {}", first.get(..10).unwrap_or(first)));
+    }
+    if let Some(age) = current_since.and_then(age) {
+        title.push(format!("current {age}"));
+    } else if !timeline.entries.is_empty() {
+        title.push("removed".to_string());
+    }
+    let title = match title.is_empty() {
+        true => "History".to_string(),
+        false => format!("History: {}", title.join(", ")),
+    };
+    let history = Table::new(
+        history,
+        [
+            Constraint::Length(10),
+            Constraint::Length(11),
+            Constraint::Min(0),
+        ],
+    )
+    .block(Block::bordered().title(title));
+
+    let areas = Layout::horizontal([
+        Constraint::Percentage(45),
+        Constraint::Percentage(25),
+        Constraint::Percentage(30),
+    ])
+    .split(rect);
     if state.show_info {
-        f.render_widget(table, rect);
+        f.render_widget(left, areas[0]);
+        f.render_widget(right, areas[1]);
+        f.render_widget(history, areas[2]);
     }
     Ok(())
 }
@@ -273,6 +471,9 @@ fn current_pack(state: &AppState) -> Option<&Package> {
         Tab::Installed => state.installed_widget.current_package(),
         Tab::Packages => state.packages_widget.current_package(),
         Tab::Updates => state.update_widget.current_package(),
+        Tab::Pacnew => state.pacnew_widget.current_package(),
+        Tab::History => state.history_widget.current_package(),
+        Tab::Cache => state.cache_widget.current_package(),
     }
 }
 
@@ -299,6 +500,9 @@ fn draw_help(state: &mut AppState, f: &mut Frame) -> Result<(), Box<dyn Error>>
         Tab::Updates => state.update_widget.command_descriptions(),
         Tab::Installed => state.installed_widget.command_descriptions(),
         Tab::Packages => state.packages_widget.command_descriptions(),
+        Tab::Pacnew => state.pacnew_widget.command_descriptions(),
+        Tab::History => state.history_widget.command_descriptions(),
+        Tab::Cache => state.cache_widget.command_descriptions(),
     };
     let formatted = extra
         .into_iter()
@@ -340,6 +544,9 @@ fn draw_status(state: &mut AppState, f: &mut Frame, rect: Rect) -> Result<(), Bo
         Tab::Updates => state.update_widget.command_descriptions(),
         Tab::Installed => state.installed_widget.command_descriptions(),
         Tab::Packages => state.packages_widget.command_descriptions(),
+        Tab::Pacnew => state.pacnew_widget.command_descriptions(),
+        Tab::History => state.history_widget.command_descriptions(),
+        Tab::Cache => state.cache_widget.command_descriptions(),
     };
     let formatted = extra
         .into_iter()
@@ -349,15 +556,20 @@ fn draw_status(state: &mut AppState, f: &mut Frame, rect: Rect) -> Result<(), Bo
 
     text.extend(formatted.iter().map(|s| s.as_str()));
 
+    //loading progress takes the place of the message
+    let status = match &state.loader {
+        Some(loader) => loader.status(),
+        None => state.message.as_ref().to_string(),
+    };
     let layout = Layout::horizontal([
         Constraint::Percentage(100),
-        Constraint::Length(state.message.length() as u16 + 2),
+        Constraint::Length(status.chars().count() as u16 + 2),
     ])
     .split(rect);
 
     let info = Paragraph::new(text.join("  ")).style(Style::default().fg(Color::Yellow));
     f.render_widget(&info, layout[0]);
-    Text::raw(state.message.as_ref())
+    Text::raw(status)
         .style(Style::default().fg(Color::Red))
         .render(layout[1], f.buffer_mut());
 
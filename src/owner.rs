@@ -0,0 +1,160 @@
+use std::path::{Path, PathBuf};
+
+use crate::{error::AppError, structs::package::Package};
+
+/// A package owning a file, like `pacman -Qo`, or `pacman -F` for repository packages
+#[derive(Debug, Clone, PartialEq)]
+pub struct Owner {
+    pub path: String,
+    pub package: String,
+    ///set when found in the files database instead of the installed packages
+    pub repository: Option<String>,
+}
+
+/// The paths to look up for a query. Paths are made absolute, bare command names are looked
+/// up in `path_var` like a shell would. Symlinked directories are resolved as pacman does,
+/// the file itself is kept so `/usr/bin/sh` is owned by whoever ships the link
+pub fn query_paths(query: &str, path_var: Option<&str>, cwd: &Path) -> Vec<PathBuf> {
+    let query = query.trim();
+    if query.is_empty() {
+        return vec![];
+    }
+    let candidates: Vec<PathBuf> = if query.contains('/') {
+        vec![cwd.join(query)]
+    } else {
+        path_var
+            .unwrap_or_default()
+            .split(':')
+            .filter(|dir| !dir.is_empty())
+            .map(|dir| Path::new(dir).join(query))
+            .filter(|p| p.is_file())
+            .collect()
+    };
+    let mut paths = vec![];
+    for path in candidates {
+        let resolved = match (path.parent(), path.file_name()) {
+            (Some(parent), Some(name)) => parent
+                .canonicalize()
+                .map(|p| p.join(name))
+                .unwrap_or_else(|_| path.clone()),
+            _ => path.clone(),
+        };
+        for p in [path, resolved] {
+            if !paths.contains(&p) {
+                paths.push(p);
+            }
+        }
+    }
+    paths
+}
+
+/// The owners found, and the packages whose file list could not be read
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OwnerScan {
+    pub owners: Vec<Owner>,
+    ///`package: error` of each package that was skipped
+    pub unreadable: Vec<String>,
+}
+
+/// Find the installed packages whose file list contains any of the paths.
+/// Like `pacman -Qo`, a package whose file list can't be read is skipped
+pub fn find_owners<'a>(
+    paths: &[PathBuf],
+    installed: impl Iterator<Item = &'a Package>,
+    files: impl Fn(&Package) -> Result<Vec<String>, AppError>,
+) -> OwnerScan {
+    let wanted: Vec<String> = paths
+        .iter()
+        .map(|p| p.to_string_lossy().trim_end_matches('/').to_string())
+        .collect();
+    let mut scan = OwnerScan::default();
+    for pack in installed {
+        let files = match files(pack) {
+            Ok(files) => files,
+            Err(e) => {
+                scan.unreadable.push(format!("{}: {e}", pack.name));
+                continue;
+            }
+        };
+        for file in files {
+            //directories are listed with a trailing slash
+            let file = file.trim_end_matches('/');
+            if wanted.iter().any(|w| w == file) {
+                scan.owners.push(Owner {
+                    path: file.to_string(),
+                    package: pack.name.clone(),
+                    repository: None,
+                });
+            }
+        }
+    }
+    scan.owners
+        .sort_by(|a, b| (&a.path, &a.package).cmp(&(&b.path, &b.package)));
+    scan.owners.dedup();
+    scan
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::{PackageSource, fixture::FixtureSource};
+
+    #[test]
+    fn test_query_paths() {
+        let dir = std::env::temp_dir().join(format!("ms-owner-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("bin")).unwrap();
+        std::fs::write(dir.join("bin/mytool"), "").unwrap();
+        let dir = dir.canonicalize().unwrap();
+        let path_var = format!("/nonexistent:{}", dir.join("bin").display());
+
+        assert_eq!(
+            query_paths("mytool", Some(&path_var), Path::new("/")),
+            vec![dir.join("bin/mytool")]
+        );
+        assert!(query_paths("othertool", Some(&path_var), Path::new("/")).is_empty());
+        assert_eq!(
+            query_paths("bin/mytool", None, &dir),
+            vec![dir.join("bin/mytool")]
+        );
+        assert!(query_paths("  ", None, &dir).is_empty());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_find_owners() {
+        let source = FixtureSource::new(&Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures"));
+        let installed = source.installed().unwrap();
+        let owners = |paths: &[&str]| {
+            let paths: Vec<PathBuf> = paths.iter().map(PathBuf::from).collect();
+            find_owners(&paths, installed.iter(), |p| source.files(p)).owners
+        };
+        let owner = |path: &str, package: &str| Owner {
+            path: path.to_string(),
+            package: package.to_string(),
+            repository: None,
+        };
+
+        assert_eq!(owners(&["/usr/bin/sh"]), vec![owner("/usr/bin/sh", "bash")]);
+        assert_eq!(
+            owners(&["/usr/lib/libc.so.6", "/usr/bin/which"]),
+            vec![
+                owner("/usr/bin/which", "which"),
+                owner("/usr/lib/libc.so.6", "glibc")
+            ]
+        );
+        //directories are shared
+        assert_eq!(owners(&["/usr/lib/"]).len(), 2);
+        assert!(owners(&["/etc/passwd"]).is_empty());
+
+        //an unreadable package is reported, the others are still searched
+        let scan = find_owners(&[PathBuf::from("/usr/bin/sh")], installed.iter(), |p| {
+            if p.name == "glibc" {
+                Err(AppError::Other("no files".to_string()))
+            } else {
+                source.files(p)
+            }
+        });
+        assert_eq!(scan.owners, vec![owner("/usr/bin/sh", "bash")]);
+        assert_eq!(scan.unreadable, vec!["glibc: Error: no files"]);
+    }
+}
@@ -0,0 +1,157 @@
+use std::{
+    fmt::Display,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::diff::unified_diff;
+
+/// Written by pacman next to a modified config file instead of overwriting it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PacFileKind {
+    ///the new version from an upgrade, the live file is the user's
+    Pacnew,
+    ///the user's version saved on removal, the live file is the package's
+    Pacsave,
+}
+
+impl Display for PacFileKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PacFileKind::Pacnew => write!(f, "pacnew"),
+            PacFileKind::Pacsave => write!(f, "pacsave"),
+        }
+    }
+}
+
+/// A `.pacnew` or `.pacsave` of a backup file owned by an installed package
+#[derive(Debug, Clone, PartialEq)]
+pub struct PacFile {
+    pub package: String,
+    pub kind: PacFileKind,
+    ///the config file in use
+    pub live: PathBuf,
+    ///the `.pacnew` or `.pacsave` next to it
+    pub path: PathBuf,
+}
+
+impl PacFile {
+    /// Unified diff from the live file to this one, so it shows what replacing would change
+    pub fn diff(&self) -> Result<Vec<String>, std::io::Error> {
+        //a pacsave may be left after the live file is gone
+        let live = match fs::read_to_string(&self.live) {
+            Ok(live) => live,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e),
+        };
+        let other = fs::read_to_string(&self.path)?;
+        Ok(unified_diff(
+            &live,
+            &other,
+            &self.live.to_string_lossy(),
+            &self.path.to_string_lossy(),
+        ))
+    }
+}
+
+/// The pacfiles found, and the packages whose backup files could not be read
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PacfileScan {
+    pub pacfiles: Vec<PacFile>,
+    ///`package: error` of each package that was skipped
+    pub unreadable: Vec<String>,
+}
+
+/// Look for pacnew and pacsave files next to each backup file under `root`.
+/// `backups` is each package with its backup paths, `.pacsave.1` etc are found too
+pub fn find_pacfiles(root: &Path, backups: &[(String, Vec<String>)]) -> Vec<PacFile> {
+    let mut found = vec![];
+    for (package, files) in backups {
+        for file in files {
+            let live = root.join(file.trim_start_matches('/'));
+            let (Some(dir), Some(name)) = (live.parent(), live.file_name()) else {
+                continue;
+            };
+            let name = name.to_string_lossy();
+            let Ok(entries) = fs::read_dir(dir) else {
+                continue;
+            };
+            let mut candidates: Vec<(PacFileKind, PathBuf)> = entries
+                .filter_map(|e| e.ok())
+                .filter_map(|e| {
+                    let other = e.file_name().to_string_lossy().to_string();
+                    let suffix = other.strip_prefix(name.as_ref())?;
+                    let kind = match suffix {
+                        ".pacnew" => PacFileKind::Pacnew,
+                        ".pacsave" => PacFileKind::Pacsave,
+                        s if s
+                            .strip_prefix(".pacsave.")
+                            .is_some_and(|n| n.chars().all(|c| c.is_ascii_digit())) =>
+                        {
+                            PacFileKind::Pacsave
+                        }
+                        _ => return None,
+                    };
+                    Some((kind, e.path()))
+                })
+                .collect();
+            candidates.sort_by(|a, b| a.1.cmp(&b.1));
+            found.extend(candidates.into_iter().map(|(kind, path)| PacFile {
+                package: package.clone(),
+                kind,
+                live: live.clone(),
+                path,
+            }));
+        }
+    }
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_pacfiles() {
+        let root = std::env::temp_dir().join(format!("ms-pacnew-{}", std::process::id()));
+        fs::create_dir_all(root.join("etc")).unwrap();
+        for name in [
+            "pacman.conf",
+            "pacman.conf.pacnew",
+            "pacman.conf.pacsave.1",
+            "pacman.conf.bak",
+            "makepkg.conf",
+        ] {
+            fs::write(root.join("etc").join(name), "").unwrap();
+        }
+        let backups = vec![
+            (
+                "pacman".to_string(),
+                vec![
+                    "/etc/pacman.conf".to_string(),
+                    "/etc/makepkg.conf".to_string(),
+                ],
+            ),
+            ("gone".to_string(), vec!["/etc/missing/file".to_string()]),
+        ];
+        let found = find_pacfiles(&root, &backups);
+        assert_eq!(
+            found,
+            vec![
+                PacFile {
+                    package: "pacman".to_string(),
+                    kind: PacFileKind::Pacnew,
+                    live: root.join("etc/pacman.conf"),
+                    path: root.join("etc/pacman.conf.pacnew"),
+                },
+                PacFile {
+                    package: "pacman".to_string(),
+                    kind: PacFileKind::Pacsave,
+                    live: root.join("etc/pacman.conf"),
+                    path: root.join("etc/pacman.conf.pacsave.1"),
+                },
+            ]
+        );
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
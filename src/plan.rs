@@ -0,0 +1,388 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+};
+
+use crate::{
+    graph::PackageGraph,
+    structs::{package::Package, reason::Reason},
+};
+
+/// The different ways pacman can remove packages
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum RemoveMode {
+    #[default]
+    Plain, //-R only the targets
+    Recursive,       //-Rs also dependencies no longer needed
+    RecursiveNoSave, //-Rns as -Rs, without keeping .pacsave files
+    Cascade,         //-Rc also everything that depends on the targets
+}
+
+impl RemoveMode {
+    pub fn values() -> [RemoveMode; 4] {
+        [
+            RemoveMode::Plain,
+            RemoveMode::Recursive,
+            RemoveMode::RecursiveNoSave,
+            RemoveMode::Cascade,
+        ]
+    }
+    pub fn flag(&self) -> &'static str {
+        match self {
+            RemoveMode::Plain => "-R",
+            RemoveMode::Recursive => "-Rs",
+            RemoveMode::RecursiveNoSave => "-Rns",
+            RemoveMode::Cascade => "-Rc",
+        }
+    }
+}
+
+impl Display for RemoveMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.flag())
+    }
+}
+
+/// What removing a set of packages would do, for each `RemoveMode`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RemovalPlan {
+    pub targets: Vec<String>,
+    ///dependencies no longer needed, removed with -Rs/-Rns
+    pub unneeded: Vec<String>,
+    ///packages depending on the targets, removed with -Rc
+    pub dependents: Vec<String>,
+    ///(installed package, removed package it needs) for -R, which pacman would refuse
+    pub breaks: Vec<(String, String)>,
+    ///same as breaks, for -Rs/-Rns
+    pub breaks_recursive: Vec<(String, String)>,
+    sizes: HashMap<String, usize>,
+}
+
+impl RemovalPlan {
+    pub fn new(targets: &[String], graph: &PackageGraph) -> Self {
+        let targets: Vec<usize> = targets
+            .iter()
+            .filter_map(|t| {
+                graph
+                    .resolve_index(t)
+                    .filter(|&i| graph.package(i).name == *t)
+            })
+            .collect();
+
+        //-Rs: keep adding dependencies that were installed as a dependency and are only needed by removed packages
+        let mut removed: HashSet<usize> = targets.iter().copied().collect();
+        let mut unneeded = vec![];
+        loop {
+            let mut added = vec![];
+            for &i in removed.iter() {
+                for &dep in graph.dependencies(i) {
+                    if !removed.contains(&dep)
+                        && !added.contains(&dep)
+                        && graph.package(dep).reason == Reason::Dependency
+                        && graph.required_by(dep).iter().all(|r| removed.contains(r))
+                    {
+                        added.push(dep);
+                    }
+                }
+            }
+            if added.is_empty() {
+                break;
+            }
+            unneeded.extend(added.iter().copied());
+            removed.extend(added);
+        }
+
+        //-Rc: everything that (indirectly) depends on the targets
+        let mut cascade: HashSet<usize> = targets.iter().copied().collect();
+        let mut dependents = vec![];
+        let mut todo = targets.clone();
+        while let Some(i) = todo.pop() {
+            for &req in graph.required_by(i) {
+                if cascade.insert(req) {
+                    dependents.push(req);
+                    todo.push(req);
+                }
+            }
+        }
+
+        let name = |i: &usize| graph.package(*i).name.clone();
+        let names = |indices: &[usize]| {
+            let mut names: Vec<String> = indices.iter().map(name).collect();
+            names.sort();
+            names
+        };
+        let breaks_for = |removed: &HashSet<usize>| {
+            let mut breaks = vec![];
+            for i in removed {
+                for req in graph.required_by(*i) {
+                    if !removed.contains(req) {
+                        breaks.push((name(req), name(i)));
+                    }
+                }
+            }
+            breaks.sort();
+            breaks
+        };
+        let plain: HashSet<usize> = targets.iter().copied().collect();
+
+        Self {
+            breaks: breaks_for(&plain),
+            breaks_recursive: breaks_for(&removed),
+            sizes: removed
+                .iter()
+                .chain(&cascade)
+                .map(|&i| (name(&i), graph.package(i).installed_size))
+                .collect(),
+            targets: targets.iter().map(name).collect(),
+            unneeded: names(&unneeded),
+            dependents: names(&dependents),
+        }
+    }
+
+    /// Packages removed in the given mode, including the targets
+    pub fn removed(&self, mode: RemoveMode) -> Vec<&String> {
+        let extra = match mode {
+            RemoveMode::Plain => &[][..],
+            RemoveMode::Recursive | RemoveMode::RecursiveNoSave => &self.unneeded[..],
+            RemoveMode::Cascade => &self.dependents[..],
+        };
+        self.targets.iter().chain(extra).collect()
+    }
+
+    /// Packages that would be left with a missing dependency
+    pub fn broken(&self, mode: RemoveMode) -> &[(String, String)] {
+        match mode {
+            RemoveMode::Plain => &self.breaks,
+            RemoveMode::Recursive | RemoveMode::RecursiveNoSave => &self.breaks_recursive,
+            RemoveMode::Cascade => &[],
+        }
+    }
+
+    /// Disk space freed in bytes
+    pub fn freed(&self, mode: RemoveMode) -> usize {
+        self.removed(mode)
+            .iter()
+            .filter_map(|name| self.sizes.get(*name))
+            .sum()
+    }
+}
+
+/// What installing a set of packages from the sync repositories would pull in
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct InstallPlan {
+    pub targets: Vec<String>,
+    ///(new dependency, package that needs it) in install order
+    pub dependencies: Vec<(String, String)>,
+    ///(installed dependency only satisfied by its repository version, package that needs it)
+    pub upgrades: Vec<(String, String)>,
+    ///(dependency, package that needs it) that no repository provides
+    pub missing: Vec<(String, String)>,
+    ///(package to install, installed package it conflicts with)
+    pub conflicts: Vec<(String, String)>,
+    pub download_size: usize,
+    pub installed_size: usize,
+}
+
+impl InstallPlan {
+    /// `graph` has both installed and sync packages, as in `AppState::packages`
+    pub fn new(targets: &[String], graph: &PackageGraph) -> Self {
+        let mut plan = InstallPlan::default();
+
+        let mut queue: Vec<&Package> = vec![];
+        for target in targets {
+            if let Some(pack) = graph.get(target) {
+                plan.targets.push(target.clone());
+                queue.push(pack);
+            }
+        }
+        let mut new: Vec<&Package> = queue.clone();
+
+        let mut i = 0;
+        while i < queue.len() {
+            let pack = queue[i];
+            i += 1;
+            for dep in &pack.dependencies {
+                if graph.satisfying(dep).any(|p| p.installed.is_some())
+                    || new.iter().any(|p| p.satisfies(dep))
+                {
+                    continue;
+                }
+                //installed but outdated, pacman pulls in the repository version.
+                //packages only keep the installed copy, with the repository version in new_version
+                let upgrade = graph.get(&dep.name).filter(|p| {
+                    p.installed.is_some()
+                        && p.new_version
+                            .as_deref()
+                            .is_some_and(|v| dep.is_satisfied_by(v))
+                });
+                if let Some(upgrade) = upgrade {
+                    if !plan.upgrades.iter().any(|(name, _)| *name == upgrade.name) {
+                        plan.upgrades
+                            .push((upgrade.name.clone(), pack.name.clone()));
+                        plan.download_size += upgrade.new_version_size.unwrap_or_default();
+                    }
+                    continue;
+                }
+                //prefer the package with the same name, then any provider, like pacman
+                match graph.satisfying(dep).next() {
+                    Some(provider) => {
+                        plan.dependencies
+                            .push((provider.name.clone(), pack.name.clone()));
+                        new.push(provider);
+                        queue.push(provider);
+                    }
+                    None => plan.missing.push((dep.to_string(), pack.name.clone())),
+                }
+            }
+        }
+
+        for pack in &new {
+            if pack.installed.is_none() {
+                plan.download_size += pack.download_size;
+                plan.installed_size += pack.installed_size;
+            }
+            for old in graph.installed() {
+                if old.name == pack.name {
+                    continue; //upgrade, not a conflict
+                }
+                if pack.conflicts.iter().any(|c| old.satisfies(c))
+                    || old.conflicts.iter().any(|c| pack.satisfies(c))
+                {
+                    plan.conflicts.push((pack.name.clone(), old.name.clone()));
+                }
+            }
+        }
+        plan
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{db::local::read_local_packages, structs::dependency::Dependency};
+    use std::path::Path;
+
+    fn graph() -> PackageGraph {
+        PackageGraph::new(installed())
+    }
+
+    fn installed() -> Vec<Package> {
+        read_local_packages(&Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures")).unwrap()
+    }
+
+    #[test]
+    fn test_remove_leaf() {
+        let plan = RemovalPlan::new(&["which".to_string()], &graph());
+        assert_eq!(plan.removed(RemoveMode::Plain), vec!["which"]);
+        //bash provides sh and is only needed by which, readline only by bash. glibc is explicit
+        assert_eq!(
+            plan.removed(RemoveMode::Recursive),
+            vec!["which", "bash", "readline"]
+        );
+        assert_eq!(plan.removed(RemoveMode::Cascade), vec!["which"]);
+        assert!(plan.broken(RemoveMode::Plain).is_empty());
+        assert_eq!(plan.freed(RemoveMode::Plain), 30000);
+        assert_eq!(plan.freed(RemoveMode::Recursive), 30000 + 9453056 + 950000);
+    }
+
+    #[test]
+    fn test_remove_needed() {
+        let plan = RemovalPlan::new(&["glibc".to_string()], &graph());
+        assert_eq!(
+            plan.broken(RemoveMode::Plain),
+            &[
+                ("bash".to_string(), "glibc".to_string()),
+                ("readline".to_string(), "glibc".to_string())
+            ]
+        );
+        assert_eq!(
+            plan.removed(RemoveMode::Cascade),
+            vec!["glibc", "bash", "readline", "which"]
+        );
+        assert!(plan.broken(RemoveMode::Cascade).is_empty());
+    }
+
+    fn dep(name: &str, version: &str, deps: &[&str]) -> Package {
+        Package {
+            name: name.to_string(),
+            version: version.to_string(),
+            dependencies: deps.iter().map(|d| Dependency::from(*d)).collect(),
+            download_size: 100,
+            installed_size: 1000,
+            ..Default::default()
+        }
+    }
+
+    fn packages() -> Vec<Package> {
+        let mut installed = installed();
+        installed.extend([
+            dep(
+                "htop",
+                "3.3.0-3",
+                &["libncursesw.so=6-64", "libnl", "glibc"],
+            ),
+            dep("libnl", "3.9.0-1", &["glibc"]),
+            Package {
+                provides: vec![Dependency::from("libncursesw.so=6-64")],
+                ..dep("ncurses", "6.5-3", &["glibc", "gcc-libs"])
+            },
+            Package {
+                conflicts: vec![Dependency::from("which")],
+                ..dep("which-ng", "1.0-1", &[])
+            },
+        ]);
+        installed
+    }
+
+    #[test]
+    fn test_install() {
+        let plan = InstallPlan::new(&["htop".to_string()], &PackageGraph::new(packages()));
+        assert_eq!(plan.targets, vec!["htop"]);
+        assert_eq!(
+            plan.dependencies,
+            vec![
+                ("ncurses".to_string(), "htop".to_string()),
+                ("libnl".to_string(), "htop".to_string())
+            ]
+        );
+        assert_eq!(
+            plan.missing,
+            vec![("gcc-libs".to_string(), "ncurses".to_string())]
+        );
+        assert!(plan.conflicts.is_empty());
+        assert_eq!(plan.download_size, 300);
+        assert_eq!(plan.installed_size, 3000);
+
+        assert!(plan.upgrades.is_empty());
+
+        //a later repository with the same package does not replace the first one
+        let mut repos = packages();
+        repos.push(Package {
+            download_size: 5000,
+            ..dep("libnl", "3.10.0-1", &["glibc"])
+        });
+        let plan = InstallPlan::new(&["htop".to_string()], &PackageGraph::new(repos));
+        assert_eq!(plan.download_size, 300);
+
+        //glibc 2.39 is installed, the repository has 2.40
+        let mut packages = packages();
+        let glibc = packages.iter_mut().find(|p| p.name == "glibc").unwrap();
+        glibc.new_version = Some("2.40-1".to_string());
+        glibc.new_version_size = Some(500);
+        packages.push(dep("nvim", "0.10.0-1", &["glibc>=2.40"]));
+        let graph = PackageGraph::new(packages);
+        let plan = InstallPlan::new(&["nvim".to_string()], &graph);
+        assert_eq!(
+            plan.upgrades,
+            vec![("glibc".to_string(), "nvim".to_string())]
+        );
+        assert!(plan.missing.is_empty());
+        assert_eq!(plan.download_size, 600);
+
+        let plan = InstallPlan::new(&["which-ng".to_string()], &graph);
+        assert_eq!(
+            plan.conflicts,
+            vec![("which-ng".to_string(), "which".to_string())]
+        );
+    }
+}
@@ -1,75 +1,179 @@
 use std::{
+    cmp::Ordering,
     collections::{HashMap, HashSet},
     io::Write,
+    path::{Path, PathBuf},
     process::Command,
+    sync::Arc,
+    time::Duration,
 };
 
 use crate::{
+    aur::AurPackage,
+    cache::{CachedPackage, find_cached},
+    db::{
+        conf::PacmanConf,
+        files::FilesDb,
+        local::{read_local_backup, read_local_files, read_local_mtree, read_local_packages},
+        log::{Transaction, read_log},
+        sync::read_sync_packages,
+    },
     error::AppError,
+    graph::PackageGraph,
+    integrity::{FileIssue, check_files},
+    loader::{LoadEvent, LoadStage, Loader, spawn_job},
+    owner::find_owners,
+    pacnew::{PacfileScan, find_pacfiles},
+    source::PackageSource,
     structs::{
-        appstate::AppState, event::EventCommand, package::Package, packageupdate::PackageUpdate,
+        appstate::AppState,
+        dependency::Dependency,
+        event::{BackgroundJob, EventCommand},
+        package::Package,
+        packageupdate::PackageUpdate,
         reason::Reason,
+        timedstring::TimedString,
     },
     update_tables,
     utils::natural_cmp,
-    version::Version,
+    version::{ChangeType, Version, vercmp},
 };
 
-pub fn refresh_packages_and_update_tables(state: &mut AppState) -> Result<(), AppError> {
-    //run these in parallel
-    let jh1 = std::thread::spawn(get_installed_packages);
-    let jh2 = std::thread::spawn(get_all_packages);
-    let jh3 = std::thread::spawn(get_updates);
+/// Start loading the packages in the background, the current data stays until it is done
+pub fn start_refresh(state: &mut AppState) {
+    state.loader = Some(Loader::spawn(state.source.clone(), state.events.sender()));
+}
+
+/// Run a job a widget asked for in the background
+pub fn start_job(state: &AppState, job: BackgroundJob) {
+    let source = state.source.clone();
+    let graph = state.packages.clone();
+    spawn_job(state.events.sender(), move || match job {
+        BackgroundJob::FilesDb => LoadEvent::FilesDb(source.sync_files()),
+        BackgroundJob::CheckFiles(packs) => LoadEvent::FileIssues(
+            packs
+                .into_iter()
+                .map(|pack| {
+                    let issues = source.check_files(&pack);
+                    (pack.name, issues)
+                })
+                .collect(),
+        ),
+        BackgroundJob::FindOwners(paths) => {
+            let scan = find_owners(&paths, graph.installed(), |p| source.files(p));
+            LoadEvent::Owners(paths, scan)
+        }
+        BackgroundJob::Pacfiles => LoadEvent::Pacfiles(source.pacfiles()),
+        BackgroundJob::History => LoadEvent::History(source.history()),
+        BackgroundJob::Cache => LoadEvent::Cache(source.cache()),
+    });
+}
 
-    //now join threads
-    let installed = jh1.join().expect("Thread error")?;
-    let all = jh2.join().expect("Thread error")?;
-    let updates = jh3.join().expect("Thread error")?;
+/// Swap in the new packages once the background refresh has finished,
+/// and hand finished jobs to their widget
+pub fn handle_load_event(state: &mut AppState, event: LoadEvent) -> Result<(), AppError> {
+    let event = match event {
+        LoadEvent::FilesDb(res) => {
+            if let Some(message) = state.packages_widget.set_files_db(res) {
+                state.message = TimedString::new(&message, Duration::from_secs(10));
+            }
+            return Ok(());
+        }
+        LoadEvent::FileIssues(results) => {
+            state.installed_widget.set_file_issues(results);
+            return Ok(());
+        }
+        LoadEvent::Owners(paths, scan) => {
+            state.installed_widget.set_owners(&paths, scan);
+            return Ok(());
+        }
+        LoadEvent::Pacfiles(res) => {
+            state.pacnew_widget.set_pacfiles(res);
+            return Ok(());
+        }
+        LoadEvent::History(res) => {
+            //a missing or unreadable log just leaves the info panel's timeline empty
+            state.history = Some(res.as_ref().cloned().unwrap_or_default());
+            state.history_loading = false;
+            state.history_widget.set_history(res);
+            return Ok(());
+        }
+        LoadEvent::Cache(res) => {
+            state.cache_widget.set_cache(res);
+            return Ok(());
+        }
+        event => event,
+    };
+    let Some(loader) = state.loader.as_mut() else {
+        return Ok(());
+    };
+    let Some(res) = loader.handle(event) else {
+        return Ok(());
+    };
+    let warnings = loader.warnings().join(", ");
+    state.loader = None;
+    state.packages = Arc::new(res?);
+    update_tables(state);
+    if !warnings.is_empty() {
+        state.message = TimedString::new(&warnings, Duration::from_secs(10));
+    }
+    Ok(())
+}
 
+/// Load everything into a graph, `progress` is told of each finished stage and `warn` of
+/// problems that only leave something out
+pub fn load_packages(
+    source: &dyn PackageSource,
+    progress: &(dyn Fn(LoadStage) + Sync),
+    warn: &dyn Fn(String),
+) -> Result<PackageGraph, AppError> {
+    //run these in parallel
+    let (installed, all, updates) = std::thread::scope(|s| {
+        let jh1 = s.spawn(|| {
+            source
+                .installed()
+                .inspect(|_| progress(LoadStage::Installed))
+        });
+        let jh2 = s.spawn(|| source.sync().inspect(|_| progress(LoadStage::Sync)));
+        let jh3 = s.spawn(|| source.updates().inspect(|_| progress(LoadStage::Updates)));
+
+        //now join threads
+        (
+            jh1.join().expect("Thread error"),
+            jh2.join().expect("Thread error"),
+            jh3.join().expect("Thread error"),
+        )
+    });
     //get sizes once we have the updates
-    let sizes = get_update_size()?;
+    let sizes = source.update_sizes()?;
+    progress(LoadStage::Sizes);
 
-    state.packages = combine_packages(installed, all, updates, sizes);
+    let mut combined = combine_packages(installed?, all?, updates?, sizes);
+    //an unreachable AUR only leaves out the AUR details, the rest is still shown
+    let foreign: Vec<String> = combined
+        .iter()
+        .filter(|p| p.installed.is_some() && p.repository.is_none())
+        .map(|p| p.name.clone())
+        .collect();
+    match source.aur_info(&foreign) {
+        Ok(aur) => add_aur_info(&mut combined, aur),
+        Err(e) => warn(format!("AUR lookup failed: {e}")),
+    }
+    progress(LoadStage::Aur);
 
-    update_tables(state);
-    Ok(())
+    let mut graph = PackageGraph::new(combined);
+    graph.count_dependencies();
+    Ok(graph)
 }
 
 pub fn run_command(state: &mut AppState, command: EventCommand) -> Result<(), AppError> {
-    let (comm, mut args, needs_package_list, packs) = match command {
-        EventCommand::RemoveSelected(packs) => ("pacman", vec!["-R"], true, packs),
-        EventCommand::InstallOrUpdateSelected(packs) => ("pacman", vec!["-S"], true, packs),
-        EventCommand::QuerySelected(packs) => ("pacman", vec!["-Qi"], true, packs),
-        EventCommand::SyncDatabase => ("pacman", vec!["-Sy"], false, vec![]),
-        EventCommand::SyncAndUpdateAll => ("pacman", vec!["-Syu"], false, vec![]),
-    };
-    if needs_package_list && packs.is_empty() {
-        return Err(String::from("No packages selected").into());
-    }
+    let ret = state.source.run(&command, &state.packages.aur_installed());
 
-    args.extend(packs.iter().map(|a| a.as_str()));
-
-    std::io::stdout()
-        .write_all(format!("\nRunning command: {} {}\n", comm, args.join(" ")).as_bytes())?;
-    //try run command as is
-    let res = Command::new(comm).args(&args).status()?;
-    let mut ret = Ok(());
-    if !res.success() {
-        std::io::stdout().write_all("running sudo\n".as_bytes())?;
-        //run as sudo
-        args.insert(0, comm);
-        args.insert(0, "-S"); //for sudo
-        let res = Command::new("sudo").args(&args).status()?;
-        if !res.success() {
-            std::io::stdout().write_all("Failed to run command".as_bytes())?;
-            ret = Err(String::from("Failed to run command").into());
-        }
-    }
     std::io::stdout().write_all("\nPress enter to continue...".as_bytes())?;
     std::io::stdout().flush()?;
     ratatui::crossterm::event::read()?;
 
-    refresh_packages_and_update_tables(state)?;
+    start_refresh(state);
 
     ret
 }
@@ -87,15 +191,37 @@ pub fn combine_packages(
         .collect::<HashSet<_>>();
     let mut combined = installed;
     //we now add all local packages not installed
+    let mut repositories: HashMap<&str, &Package> = HashMap::new();
     for pack in all.iter() {
         if !installed_names.contains(&pack.name) {
             combined.push(pack.clone());
+        } else {
+            repositories.entry(&pack.name).or_insert(pack);
+        }
+    }
+    //installed packages come from the first repository that has them
+    for pack in combined.iter_mut() {
+        let Some(repo_pack) = repositories.get(pack.name.as_str()) else {
+            continue;
+        };
+        pack.repository = repo_pack.repository.clone();
+        //pacman does not list downgrades as updates, so we find them ourselves
+        if vercmp(&repo_pack.version, &pack.version) == Ordering::Less {
+            pack.new_version = Some(repo_pack.version.clone());
+            pack.change_type = Some(ChangeType::Downgrade);
+            pack.new_version_size = Some(repo_pack.download_size);
         }
     }
 
     //we add update info
+    let index: HashMap<String, usize> = combined
+        .iter()
+        .enumerate()
+        .map(|(i, p)| (p.name.clone(), i))
+        .rev() //keep the first, which is the installed one
+        .collect();
     for pack in updates.iter() {
-        if let Some(p) = combined.iter_mut().find(|p| p.name == pack.name) {
+        if let Some(p) = index.get(&pack.name).map(|&i| &mut combined[i]) {
             p.new_version = Some(pack.new_version.clone());
             p.change_type = Some(pack.change_type.clone());
             //add size
@@ -109,23 +235,56 @@ pub fn combine_packages(
     combined
 }
 
-pub fn get_provides(pack_name: &str) -> Result<Vec<String>, AppError> {
-    let output = Command::new("pacman").arg("-Ql").arg(pack_name).output()?;
-    if !output.status.success() {
-        return Err(AppError::CommandNonZero(output.status.code()));
+/// Attach AUR details to foreign packages, a newer AUR version is an update like a repository one
+pub fn add_aur_info(packages: &mut [Package], aur: Vec<AurPackage>) {
+    let aur: HashMap<String, AurPackage> = aur.into_iter().map(|a| (a.name.clone(), a)).collect();
+    for pack in packages.iter_mut().filter(|p| p.repository.is_none()) {
+        let Some(info) = aur.get(&pack.name) else {
+            continue;
+        };
+        if pack.installed.is_some() && vercmp(&info.version, &pack.version) == Ordering::Greater {
+            pack.new_version = Some(info.version.clone());
+            pack.change_type = Some(
+                Version::from(pack.version.as_str())
+                    .change_type(&Version::from(info.version.as_str())),
+            );
+        }
+        pack.aur = Some(info.clone());
     }
-    let output = String::from_utf8(output.stdout)?;
-    let vals = output
-        .lines()
-        .filter_map(|line| {
-            if let Some((_, path)) = line.split_once(" ") {
-                Some(path.to_string())
-            } else {
-                None
-            }
-        })
-        .collect::<Vec<String>>();
-    Ok(vals)
+}
+
+pub fn get_files(db_path: &Path, pack: &Package) -> Result<Vec<String>, AppError> {
+    read_local_files(db_path, pack)
+}
+/// Check the installed files of a package against its mtree, like `pacman -Qkk`
+pub fn get_file_issues(
+    db_path: &Path,
+    root: &Path,
+    pack: &Package,
+) -> Result<Vec<FileIssue>, AppError> {
+    let mtree = read_local_mtree(db_path, pack)?;
+    Ok(check_files(
+        root,
+        &mtree,
+        &read_local_backup(db_path, pack)?,
+    ))
+}
+/// Pacnew and pacsave files of the backup files of every installed package.
+/// A package whose `files` entry cannot be read is skipped and reported
+pub fn get_pacfiles(db_path: &Path, root: &Path) -> Result<PacfileScan, AppError> {
+    let mut backups = vec![];
+    let mut unreadable = vec![];
+    for pack in read_local_packages(db_path)? {
+        match read_local_backup(db_path, &pack) {
+            Ok(files) if files.is_empty() => {}
+            Ok(files) => backups.push((pack.name, files)),
+            Err(e) => unreadable.push(format!("{}: {e}", pack.name)),
+        }
+    }
+    Ok(PacfileScan {
+        pacfiles: find_pacfiles(root, &backups),
+        unreadable,
+    })
 }
 pub fn get_update_size() -> Result<HashMap<String, usize>, AppError> {
     let output = Command::new("pacman")
@@ -163,6 +322,20 @@ pub fn pacman_exists() -> bool {
     Command::new("pacman").output().is_ok()
 }
 
+/// Whether mspacman runs with an effective uid of root
+pub fn is_root() -> bool {
+    std::fs::read_to_string("/proc/self/status")
+        .ok()
+        .and_then(|status| effective_uid(&status))
+        == Some(0)
+}
+
+///second field of the `Uid:` line, after the real uid
+fn effective_uid(status: &str) -> Option<u32> {
+    let uids = status.lines().find_map(|l| l.strip_prefix("Uid:"))?;
+    uids.split_whitespace().nth(1)?.parse().ok()
+}
+
 pub fn get_packages_command(command: &str) -> Result<Vec<Package>, AppError> {
     let output = Command::new("pacman")
         .env("LC_TIME", "C")
@@ -177,13 +350,13 @@ pub fn get_packages_command(command: &str) -> Result<Vec<Package>, AppError> {
     let mut in_dependencies_optional = false;
 
     for line in output.lines() {
-        //if listing provides:
+        //if listing files:
         if !pack.name.is_empty() && line.starts_with(&pack.name) {
             if let Some((_, pr)) = line.split_once(" ") {
-                if pack.provides.is_none() {
-                    pack.provides = Some(vec![]);
+                if pack.files.is_none() {
+                    pack.files = Some(vec![]);
                 }
-                if let Some(vec) = pack.provides.as_mut() {
+                if let Some(vec) = pack.files.as_mut() {
                     vec.push(pr.to_string());
                 }
             }
@@ -208,8 +381,15 @@ pub fn get_packages_command(command: &str) -> Result<Vec<Package>, AppError> {
             "Depends On" => {
                 pack.dependencies = value
                     .split_whitespace()
-                    .map(|r| r.to_string())
-                    .filter(|r| r != "None")
+                    .filter(|r| *r != "None")
+                    .map(Dependency::from)
+                    .collect()
+            }
+            "Provides" => {
+                pack.provides = value
+                    .split_whitespace()
+                    .filter(|r| *r != "None")
+                    .map(Dependency::from)
                     .collect()
             }
             "Optional Deps" => {
@@ -261,61 +441,38 @@ pub fn get_packages_command(command: &str) -> Result<Vec<Package>, AppError> {
     Ok(packs)
 }
 
-pub fn get_all_packages() -> Result<Vec<Package>, AppError> {
-    let packs = get_packages_command("-Si")?;
-    Ok(packs)
+pub fn get_all_packages(db_path: &Path, conf_path: &Path) -> Result<Vec<Package>, AppError> {
+    let repos = PacmanConf::read(conf_path)
+        .map(|c| c.repositories)
+        .unwrap_or_default();
+    match read_sync_packages(db_path, &repos) {
+        Ok(packs) if !packs.is_empty() => Ok(packs),
+        //no readable sync databases, so ask pacman
+        _ => get_packages_command("-Si"),
+    }
 }
 
-pub fn get_installed_packages() -> Result<Vec<Package>, AppError> {
-    let mut installed = get_packages_command("-Qi")?;
-    //build full recursive tree. We want to know all dependencies, and dependencies of depencencies
-    let names = installed
-        .iter()
-        .map(|a| a.name.to_string())
-        .collect::<Vec<_>>();
-    let mut cache: HashMap<String, Vec<String>> = HashMap::new();
-    let mut current: HashSet<String> = HashSet::new();
-    for pack in &names {
-        let deps = get_dependents(pack, &installed, &mut cache, &mut current);
-        if let Some(p) = installed.iter_mut().find(|a| &a.name == pack) {
-            p.dependencies_count = deps.len();
-        }
-    }
-    Ok(installed)
+/// Transactions from the `LogFile` set in pacman.conf
+pub fn get_history(conf_path: &Path) -> Result<Vec<Transaction>, AppError> {
+    let conf = PacmanConf::read(conf_path).unwrap_or_default();
+    read_log(Path::new(conf.log_file()))
 }
 
-fn get_dependents(
-    name: &str,
-    packages: &[Package],
-    cache: &mut HashMap<String, Vec<String>>,
-    current: &mut HashSet<String>, //to prevent loops
-) -> HashSet<String> {
-    let mut hs = HashSet::new();
-    if let Some(pack) = packages.iter().find(|a| a.name == name) {
-        for dep in &pack.dependencies {
-            hs.insert(dep.clone());
-            //check if in cache:
-            if let Some(cached) = cache.get(dep) {
-                for c in cached {
-                    hs.insert(c.clone());
-                }
-                continue;
-            }
+pub fn get_cache(conf_path: &Path) -> Result<Vec<CachedPackage>, AppError> {
+    let conf = PacmanConf::read(conf_path).unwrap_or_default();
+    let dirs: Vec<PathBuf> = conf.cache_dirs().iter().map(PathBuf::from).collect();
+    Ok(find_cached(&dirs))
+}
 
-            //prevent loops
-            if current.contains(dep) {
-                continue;
-            }
-            current.insert(dep.clone());
-            let sub = get_dependents(dep, packages, cache, current);
-            // println!("{dep} has {} indirect: {sub:?}", sub.len());
-            cache.insert(dep.clone(), sub.iter().cloned().collect());
-            for s in sub {
-                hs.insert(s);
-            }
-        }
-    }
-    hs
+pub fn get_sync_files(db_path: &Path, conf_path: &Path) -> Result<FilesDb, AppError> {
+    let repos = PacmanConf::read(conf_path)
+        .map(|c| c.repositories)
+        .unwrap_or_default();
+    FilesDb::read(db_path, &repos)
+}
+
+pub fn get_installed_packages(db_path: &Path) -> Result<Vec<Package>, AppError> {
+    read_local_packages(db_path)
 }
 
 pub fn get_updates() -> Result<Vec<PackageUpdate>, AppError> {
@@ -351,3 +508,58 @@ pub fn to_date(value: &str) -> Result<String, AppError> {
     let time = jiff::fmt::strtime::parse("%a %b %e %H:%M:%S %Y", value)?;
     Ok(time.to_datetime()?.to_string().replace("T", " "))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{fs, path::Path};
+
+    #[test]
+    fn test_virtual_dependencies() {
+        let installed =
+            read_local_packages(&Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures")).unwrap();
+        let mut graph = PackageGraph::new(installed);
+        assert_eq!(graph.resolve("sh").unwrap().name, "bash");
+        assert_eq!(graph.resolve("libreadline.so").unwrap().name, "readline");
+        graph.count_dependencies();
+        //which -> sh (bash) -> readline, glibc, ncurses and their dependencies
+        let which = graph.get("which").unwrap();
+        assert_eq!(which.dependencies_count, 8);
+    }
+
+    #[test]
+    fn test_get_pacfiles() {
+        let db = std::env::temp_dir().join(format!("ms-pacfiles-{}", std::process::id()));
+        let entry = |name: &str, files: Option<&str>| {
+            let dir = db.join("local").join(format!("{name}-1.0-1"));
+            fs::create_dir_all(&dir).unwrap();
+            fs::write(
+                dir.join("desc"),
+                format!("%NAME%\n{name}\n\n%VERSION%\n1.0-1\n"),
+            )
+            .unwrap();
+            if let Some(files) = files {
+                fs::write(dir.join("files"), files).unwrap();
+            }
+        };
+        entry("good", Some("%BACKUP%\netc/good.conf\tabc\n"));
+        entry("broken", None);
+        fs::create_dir_all(db.join("etc")).unwrap();
+        fs::write(db.join("etc/good.conf.pacnew"), "new").unwrap();
+
+        //the broken entry does not hide the pacnew of the other package
+        let scan = get_pacfiles(&db, &db).unwrap();
+        assert_eq!(scan.pacfiles.len(), 1);
+        assert_eq!(scan.pacfiles[0].package, "good");
+        assert_eq!(scan.unreadable.len(), 1);
+        assert!(scan.unreadable[0].starts_with("broken: "));
+        fs::remove_dir_all(&db).unwrap();
+    }
+
+    #[test]
+    fn test_effective_uid() {
+        let status = "Name:\tms\nUmask:\t0022\nUid:\t1000\t0\t0\t0\nGid:\t1000\t1000\t1000\t1000\n";
+        assert_eq!(effective_uid(status), Some(0));
+        assert_eq!(effective_uid("Name:\tms\n"), None);
+    }
+}
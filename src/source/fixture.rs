@@ -0,0 +1,327 @@
+use std::{
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    aur::{AurPackage, parse_response},
+    cache::{CachedPackage, find_cached},
+    config::{AurHelper, Config},
+    db::{
+        conf::PacmanConf,
+        files::FilesDb,
+        local::{read_local_files, read_local_packages},
+        log::{Transaction, read_log},
+        snapshot::Snapshot,
+        sync::read_sync_packages,
+    },
+    error::AppError,
+    integrity::FileIssue,
+    pacnew::{PacfileScan, find_pacfiles},
+    pman::{get_file_issues, get_pacfiles},
+    source::{PackageSource, command_args},
+    structs::{event::EventCommand, package::Package, packageupdate::PackageUpdate},
+    version::{Version, vercmp},
+};
+
+/// Loads everything from a directory laid out like the pacman database:
+/// `local/<name>-<version>/{desc,files,mtree}`, `sync/<repo>.db`, an optional `pacman.conf`,
+/// `pacman.log`, a package cache in `cache/` and `aur.json`, an AUR RPC response.
+/// Installed files and config files are looked for in `root/` instead of `/`.
+/// Or from a JSON snapshot of the database, with the other files next to it.
+/// Transactions are only printed, nothing is changed
+#[derive(Debug, Clone)]
+pub struct FixtureSource {
+    root: PathBuf,
+    snapshot: Option<Snapshot>,
+    aur_helper: Option<AurHelper>,
+}
+
+impl FixtureSource {
+    pub fn new(root: &Path) -> Self {
+        Self {
+            root: root.to_path_buf(),
+            snapshot: None,
+            aur_helper: None,
+        }
+    }
+
+    /// Load the databases from a JSON snapshot, see `Snapshot`
+    pub fn from_snapshot(path: &Path) -> Result<Self, AppError> {
+        let root = path.parent().unwrap_or(Path::new("."));
+        Ok(Self {
+            snapshot: Some(Snapshot::read(path)?),
+            ..Self::new(root)
+        })
+    }
+
+    /// Use the settings that decide which commands would be run
+    pub fn with_config(mut self, config: &Config) -> Self {
+        self.aur_helper = config.aur_helper.clone();
+        self
+    }
+
+    fn aur_packages(&self) -> Result<Vec<AurPackage>, AppError> {
+        let path = self.root.join("aur.json");
+        if !path.exists() {
+            return Ok(vec![]);
+        }
+        parse_response(&std::fs::read_to_string(path)?)
+    }
+
+    fn repositories(&self) -> Vec<String> {
+        PacmanConf::read(&self.root.join("pacman.conf"))
+            .map(|c| c.repositories)
+            .unwrap_or_default()
+    }
+}
+
+impl PackageSource for FixtureSource {
+    fn installed(&self) -> Result<Vec<Package>, AppError> {
+        match &self.snapshot {
+            Some(snapshot) => Ok(snapshot.installed()),
+            None => read_local_packages(&self.root),
+        }
+    }
+
+    fn sync(&self) -> Result<Vec<Package>, AppError> {
+        if let Some(snapshot) = &self.snapshot {
+            return Ok(snapshot.sync());
+        }
+        if !self.root.join("sync").exists() {
+            return Ok(vec![]);
+        }
+        read_sync_packages(&self.root, &self.repositories())
+    }
+
+    fn updates(&self) -> Result<Vec<PackageUpdate>, AppError> {
+        Ok(updates_from_sync(&self.installed()?, &self.sync()?))
+    }
+
+    fn update_sizes(&self) -> Result<HashMap<String, usize>, AppError> {
+        let updates = self.updates()?;
+        let sizes = self
+            .sync()?
+            .into_iter()
+            .filter(|p| updates.iter().any(|u| u.name == p.name))
+            .map(|p| (p.name, p.download_size))
+            .collect();
+        Ok(sizes)
+    }
+
+    fn files(&self, pack: &Package) -> Result<Vec<String>, AppError> {
+        match &self.snapshot {
+            Some(snapshot) => snapshot.files(pack),
+            None => read_local_files(&self.root, pack),
+        }
+    }
+
+    fn check_files(&self, pack: &Package) -> Result<Vec<FileIssue>, AppError> {
+        if self.snapshot.is_some() {
+            return Err(AppError::Other(format!(
+                "No mtree for {} in a snapshot",
+                pack.name
+            )));
+        }
+        get_file_issues(&self.root, &self.root.join("root"), pack)
+    }
+
+    fn pacfiles(&self) -> Result<PacfileScan, AppError> {
+        match &self.snapshot {
+            Some(snapshot) => Ok(PacfileScan {
+                pacfiles: find_pacfiles(&self.root.join("root"), &snapshot.backups()),
+                unreadable: vec![],
+            }),
+            None => get_pacfiles(&self.root, &self.root.join("root")),
+        }
+    }
+
+    fn history(&self) -> Result<Vec<Transaction>, AppError> {
+        let path = self.root.join("pacman.log");
+        if !path.exists() {
+            return Ok(vec![]);
+        }
+        read_log(&path)
+    }
+
+    fn cache(&self) -> Result<Vec<CachedPackage>, AppError> {
+        Ok(find_cached(&[self.root.join("cache")]))
+    }
+
+    fn aur_info(&self, names: &[String]) -> Result<Vec<AurPackage>, AppError> {
+        Ok(self
+            .aur_packages()?
+            .into_iter()
+            .filter(|p| names.contains(&p.name))
+            .collect())
+    }
+
+    fn aur_search(&self, term: &str) -> Result<Vec<AurPackage>, AppError> {
+        Ok(self
+            .aur_packages()?
+            .into_iter()
+            .filter(|p| {
+                p.name.contains(term) || p.description.as_ref().is_some_and(|d| d.contains(term))
+            })
+            .collect())
+    }
+
+    fn sync_files(&self) -> Result<FilesDb, AppError> {
+        if let Some(snapshot) = &self.snapshot {
+            return Ok(snapshot.sync_files());
+        }
+        if !self.root.join("sync").exists() {
+            return Ok(FilesDb::default());
+        }
+        FilesDb::read(&self.root, &self.repositories())
+    }
+
+    fn run(&self, command: &EventCommand, foreign: &HashSet<String>) -> Result<(), AppError> {
+        for invocation in command_args(command, self.aur_helper.as_ref(), foreign)? {
+            std::io::stdout()
+                .write_all(format!("\nFixture, not running command: {invocation}\n").as_bytes())?;
+        }
+        Ok(())
+    }
+}
+
+/// Like `pacman -Qu`: installed packages with a newer version in the first repository providing them
+pub fn updates_from_sync(installed: &[Package], sync: &[Package]) -> Vec<PackageUpdate> {
+    let mut latest: HashMap<&str, &Package> = HashMap::new();
+    for pack in sync {
+        latest.entry(pack.name.as_str()).or_insert(pack);
+    }
+    installed
+        .iter()
+        .filter_map(|pack| {
+            let new = latest.get(pack.name.as_str())?;
+            if vercmp(&new.version, &pack.version) != Ordering::Greater {
+                return None;
+            }
+            Some(PackageUpdate {
+                name: pack.name.clone(),
+                current_version: pack.version.clone(),
+                new_version: new.version.clone(),
+                change_type: Version::from(pack.version.as_str())
+                    .change_type(&Version::from(new.version.as_str())),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::integrity::FileProblem;
+    use crate::{
+        pman::load_packages,
+        structs::{appstate::AppState, tab::Tab},
+        version::ChangeType,
+        widgets::CurrentPackage,
+    };
+    use std::sync::Arc;
+
+    fn fixture() -> FixtureSource {
+        FixtureSource::new(&Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures"))
+    }
+
+    #[test]
+    fn test_fixture_source() {
+        let source = fixture();
+        assert_eq!(source.installed().unwrap().len(), 4);
+        assert_eq!(source.sync().unwrap().len(), 6);
+
+        let updates = source.updates().unwrap();
+        assert_eq!(updates.len(), 1);
+        assert_eq!(updates[0].name, "bash");
+        assert_eq!(updates[0].new_version, "5.2.032-1");
+        assert_eq!(source.update_sizes().unwrap().get("bash"), Some(&1900000));
+        assert!(source.sync_files().unwrap().files("zsh").is_some());
+
+        //the fixture root has which unchanged, bash's binary is missing
+        let installed = source.installed().unwrap();
+        let pack = |name: &str| installed.iter().find(|p| p.name == name).unwrap();
+        let which = source.check_files(pack("which")).unwrap();
+        assert!(which.iter().all(|i| !i.problem.is_serious()), "{which:?}");
+        let bash = source.check_files(pack("bash")).unwrap();
+        assert_eq!(bash.len(), 1);
+        assert_eq!(bash[0].path, "/usr/bin/bash");
+        assert_eq!(bash[0].problem, FileProblem::Missing);
+        //no mtree
+        assert!(source.check_files(pack("glibc")).is_err());
+
+        let pacfiles = source.pacfiles().unwrap().pacfiles;
+        assert_eq!(pacfiles.len(), 1);
+        assert_eq!(pacfiles[0].package, "bash");
+        assert!(pacfiles[0].path.ends_with("etc/bash.bashrc.pacnew"));
+
+        //signatures are not listed
+        let cached = source.cache().unwrap();
+        assert_eq!(cached.len(), 7);
+        assert_eq!(cached[0].name, "bash");
+        assert_eq!(cached[0].version, "5.2.021-1");
+        assert_eq!(cached[0].size, 2048);
+    }
+
+    #[test]
+    fn test_snapshot_source() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures/snapshot.json");
+        let source = FixtureSource::from_snapshot(&path).unwrap();
+        assert_eq!(source.installed().unwrap().len(), 2);
+        let updates = source.updates().unwrap();
+        assert_eq!(updates.len(), 1);
+        assert_eq!(updates[0].new_version, "5.2.032-1");
+        //the rest comes from next to the snapshot
+        assert_eq!(source.pacfiles().unwrap().pacfiles.len(), 1);
+        assert!(!source.history().unwrap().is_empty());
+        assert!(FixtureSource::from_snapshot(&path.with_file_name("nope.json")).is_err());
+    }
+
+    #[test]
+    fn test_fixture_widgets() {
+        let mut state = AppState::new(Arc::new(fixture()));
+        state.packages = Arc::new(load_packages(state.source.as_ref(), &|_| {}, &|_| {}).unwrap());
+        crate::update_tables(&mut state);
+
+        //installed packages are sorted by name
+        assert_eq!(state.tab, Tab::Installed);
+        let current = state.installed_widget.current_package().unwrap();
+        assert_eq!(current.name, "bash");
+        assert_eq!(current.repository.as_deref(), Some("core"));
+        assert_eq!(current.new_version.as_deref(), Some("5.2.032-1"));
+
+        state.installed_widget.goto_package_by_name("which");
+        let current = state.installed_widget.current_package().unwrap();
+        assert_eq!(current.name, "which");
+        assert!(current.repository.is_none());
+        //it is in the AUR, so it is sent to an AUR helper
+        assert_eq!(
+            state.packages.aur_installed(),
+            HashSet::from(["which".to_string()])
+        );
+
+        //packages tab has installed and sync packages
+        assert!(state.packages_widget.current_package().is_some());
+        assert_eq!(state.packages.packages().len(), 7);
+        assert_eq!(
+            state
+                .update_widget
+                .current_package()
+                .map(|p| p.name.as_str()),
+            Some("bash")
+        );
+
+        //the repo has an older readline than installed
+        let readline = state.packages.get("readline").unwrap();
+        assert_eq!(readline.new_version.as_deref(), Some("8.2.001-1"));
+        assert_eq!(readline.change_type, Some(ChangeType::Downgrade));
+
+        //which is foreign, with a newer version in the AUR
+        let which = state.packages.get("which").unwrap();
+        assert_eq!(which.new_version.as_deref(), Some("2.23-1"));
+        assert_eq!(which.change_type, Some(ChangeType::Minor));
+        assert_eq!(which.aur.as_ref().map(|a| a.num_votes), Some(42));
+    }
+}
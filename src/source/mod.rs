@@ -0,0 +1,297 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::{Debug, Display},
+    path::Path,
+};
+
+use crate::{
+    aur::AurPackage,
+    cache::CachedPackage,
+    config::AurHelper,
+    db::{files::FilesDb, log::Transaction},
+    error::AppError,
+    integrity::FileIssue,
+    pacnew::PacfileScan,
+    structs::{event::EventCommand, package::Package, packageupdate::PackageUpdate},
+};
+
+pub mod fixture;
+pub mod pacman;
+
+/// Where package information comes from, and where transactions are sent to.
+/// Everything above this goes through the trait, so it can be run without a real Arch system
+pub trait PackageSource: Debug + Send + Sync {
+    ///installed packages, with `required_by` filled in
+    fn installed(&self) -> Result<Vec<Package>, AppError>;
+    ///packages available in the sync repositories
+    fn sync(&self) -> Result<Vec<Package>, AppError>;
+    ///installed packages with a newer version available
+    fn updates(&self) -> Result<Vec<PackageUpdate>, AppError>;
+    ///download size of each package that would be updated
+    fn update_sizes(&self) -> Result<HashMap<String, usize>, AppError>;
+    ///files provided by an installed package
+    fn files(&self, pack: &Package) -> Result<Vec<String>, AppError>;
+    ///installed files that are missing or differ from the package's mtree
+    fn check_files(&self, pack: &Package) -> Result<Vec<FileIssue>, AppError>;
+    ///`.pacnew` and `.pacsave` files next to the backup files of installed packages
+    fn pacfiles(&self) -> Result<PacfileScan, AppError>;
+    ///package transactions from `pacman.log`, oldest first
+    fn history(&self) -> Result<Vec<Transaction>, AppError>;
+    ///package files in the pacman caches
+    fn cache(&self) -> Result<Vec<CachedPackage>, AppError>;
+    ///AUR details of the named packages, empty when AUR lookups are off
+    fn aur_info(&self, names: &[String]) -> Result<Vec<AurPackage>, AppError>;
+    ///search the AUR by name and description
+    fn aur_search(&self, term: &str) -> Result<Vec<AurPackage>, AppError>;
+    ///file lists of repository packages, from the local `.files` databases
+    fn sync_files(&self) -> Result<FilesDb, AppError>;
+    ///run a transaction such as install or remove, `foreign` packages from the AUR go to the
+    ///AUR helper
+    fn run(&self, command: &EventCommand, foreign: &HashSet<String>) -> Result<(), AppError>;
+}
+
+fn path_arg(path: &Path) -> String {
+    path.to_string_lossy().to_string()
+}
+
+/// One program to run for a transaction
+#[derive(Debug, Clone, PartialEq)]
+pub struct Invocation {
+    pub program: String,
+    pub args: Vec<String>,
+    ///run through the configured escalation unless already root.
+    ///AUR helpers escalate themselves
+    pub needs_root: bool,
+}
+
+impl Display for Invocation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.program, self.args.join(" "))
+    }
+}
+
+/// Programs and arguments for a transaction.
+/// With an AUR helper, `foreign` packages go to it and the rest to pacman, without one
+/// they can only be removed. `foreign` only has packages found in the AUR
+pub fn command_args(
+    command: &EventCommand,
+    helper: Option<&AurHelper>,
+    foreign: &HashSet<String>,
+) -> Result<Vec<Invocation>, AppError> {
+    let run = |program: &str, args: Vec<String>| Invocation {
+        program: program.to_string(),
+        args,
+        needs_root: command.needs_root(),
+    };
+    let with_args = |flag: &str, packs: &[String]| {
+        let mut args = vec![flag.to_string()];
+        args.extend(packs.iter().cloned());
+        args
+    };
+    let by_helper = |(program, args): (String, Vec<String>)| Invocation {
+        program,
+        args,
+        needs_root: false,
+    };
+    //repository and foreign packages
+    let split = |packs: &[String]| -> Result<(Vec<String>, Vec<String>), AppError> {
+        if packs.is_empty() {
+            return Err(String::from("No packages selected").into());
+        }
+        Ok(packs.iter().cloned().partition(|p| !foreign.contains(p)))
+    };
+    let invocations = match command {
+        EventCommand::RemoveSelected(packs, mode) => {
+            let (mut repo, aur) = split(packs)?;
+            let removal = helper
+                .filter(|_| !aur.is_empty())
+                .and_then(|h| h.remove(mode.flag(), &aur));
+            if removal.is_none() {
+                repo.extend(aur);
+            }
+            //foreign packages first, they are more likely to depend on repository ones
+            let mut invocations: Vec<Invocation> = removal.into_iter().map(by_helper).collect();
+            if !repo.is_empty() {
+                invocations.push(run("pacman", with_args(mode.flag(), &repo)));
+            }
+            invocations
+        }
+        EventCommand::InstallOrUpdateSelected(packs) => {
+            let (repo, aur) = split(packs)?;
+            //pacman cannot find foreign packages, updates from an AUR lookup need the helper
+            if helper.is_none() && !aur.is_empty() {
+                return Err(format!(
+                    "Set AurHelper to install foreign packages: {}",
+                    aur.join(" ")
+                )
+                .into());
+            }
+            //repository packages first, foreign ones may be built against them
+            let mut invocations = vec![];
+            if !repo.is_empty() {
+                invocations.push(run("pacman", with_args("-S", &repo)));
+            }
+            if let Some(helper) = helper
+                && !aur.is_empty()
+            {
+                invocations.push(by_helper(helper.install(&aur)));
+            }
+            invocations
+        }
+        EventCommand::QuerySelected(packs) => {
+            if packs.is_empty() {
+                return Err(String::from("No packages selected").into());
+            }
+            vec![run("pacman", with_args("-Qi", packs))]
+        }
+        EventCommand::SyncDatabase => vec![run("pacman", vec!["-Sy".to_string()])],
+        EventCommand::ReplaceWithPacfile(pacfile, live) => {
+            let args = vec!["-f".to_string(), path_arg(pacfile), path_arg(live)];
+            vec![run("mv", args)]
+        }
+        EventCommand::DeletePacfile(pacfile) => {
+            vec![run("rm", vec!["-f".to_string(), path_arg(pacfile)])]
+        }
+        EventCommand::InstallFile(path) => {
+            vec![run("pacman", vec!["-U".to_string(), path_arg(path)])]
+        }
+        EventCommand::DeleteCached(paths) => {
+            if paths.is_empty() {
+                return Err(String::from("No cached files to delete").into());
+            }
+            let mut args = vec!["-f".to_string()];
+            args.extend(paths.iter().map(|p| path_arg(p)));
+            vec![run("rm", args)]
+        }
+        EventCommand::SyncAndUpdateAll => {
+            let mut invocations = vec![run("pacman", vec!["-Syu".to_string()])];
+            if let Some(helper) = helper
+                && !foreign.is_empty()
+            {
+                let mut foreign: Vec<String> = foreign.iter().cloned().collect();
+                foreign.sort();
+                invocations.push(by_helper(helper.update(&foreign)));
+            }
+            invocations
+        }
+    };
+    Ok(invocations)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{graph::PackageGraph, plan::RemoveMode};
+
+    #[test]
+    fn test_command_args() {
+        let names = |n: &[&str]| n.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        let foreign: HashSet<String> = names(&["yay-bin"]).into_iter().collect();
+        let lines = |command: &EventCommand, helper: Option<&AurHelper>| {
+            command_args(command, helper, &foreign)
+                .unwrap()
+                .iter()
+                .map(|i| (i.to_string(), i.needs_root))
+                .collect::<Vec<_>>()
+        };
+
+        let install = EventCommand::InstallOrUpdateSelected(names(&["bash", "yay-bin"]));
+        assert!(command_args(&install, None, &foreign).is_err());
+        assert_eq!(
+            lines(
+                &EventCommand::InstallOrUpdateSelected(names(&["bash"])),
+                None
+            ),
+            vec![("pacman -S bash".to_string(), true)]
+        );
+        assert_eq!(
+            lines(&install, Some(&AurHelper::Paru)),
+            vec![
+                ("pacman -S bash".to_string(), true),
+                ("paru -S yay-bin".to_string(), false)
+            ]
+        );
+
+        let remove =
+            EventCommand::RemoveSelected(names(&["bash", "yay-bin"]), RemoveMode::Recursive);
+        assert_eq!(
+            lines(&remove, None),
+            vec![("pacman -Rs bash yay-bin".to_string(), true)]
+        );
+        assert_eq!(
+            lines(&remove, Some(&AurHelper::Yay)),
+            vec![
+                ("yay -Rs yay-bin".to_string(), false),
+                ("pacman -Rs bash".to_string(), true)
+            ]
+        );
+        let template = AurHelper::Template("pikaur -S {packages}".to_string());
+        assert_eq!(
+            lines(&remove, Some(&template)),
+            vec![("pacman -Rs bash yay-bin".to_string(), true)]
+        );
+
+        assert_eq!(
+            lines(&EventCommand::SyncAndUpdateAll, Some(&template)),
+            vec![
+                ("pacman -Syu".to_string(), true),
+                ("pikaur -S yay-bin".to_string(), false)
+            ]
+        );
+        assert_eq!(
+            lines(&EventCommand::QuerySelected(names(&["bash"])), None),
+            vec![("pacman -Qi bash".to_string(), false)]
+        );
+        assert!(
+            command_args(
+                &EventCommand::InstallOrUpdateSelected(vec![]),
+                None,
+                &foreign
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_command_args_local() {
+        //my-tool was built locally, the AUR lookup found nothing for it
+        let installed = |name: &str, repository: Option<&str>, aur: bool| Package {
+            name: name.to_string(),
+            installed: Some("2024-01-01 00:00:00".to_string()),
+            repository: repository.map(String::from),
+            aur: aur.then(|| AurPackage {
+                name: name.to_string(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let graph = PackageGraph::new(vec![
+            installed("bash", Some("core"), false),
+            installed("my-tool", None, false),
+            installed("yay-bin", None, true),
+        ]);
+        let foreign = graph.aur_installed();
+        assert_eq!(foreign, HashSet::from(["yay-bin".to_string()]));
+        let lines = |command: &EventCommand, helper: &AurHelper| {
+            command_args(command, Some(helper), &foreign)
+                .unwrap()
+                .iter()
+                .map(|i| i.to_string())
+                .collect::<Vec<_>>()
+        };
+
+        let template = AurHelper::Template("pikaur -S {packages}".to_string());
+        assert_eq!(
+            lines(&EventCommand::SyncAndUpdateAll, &template),
+            vec!["pacman -Syu", "pikaur -S yay-bin"]
+        );
+        let remove = EventCommand::RemoveSelected(
+            vec!["my-tool".to_string(), "yay-bin".to_string()],
+            RemoveMode::Plain,
+        );
+        assert_eq!(
+            lines(&remove, &AurHelper::Yay),
+            vec!["yay -R yay-bin", "pacman -R my-tool"]
+        );
+    }
+}
@@ -0,0 +1,211 @@
+use std::{
+    collections::{HashMap, HashSet},
+    io::{Read, Write},
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+use crate::{
+    aur::{AurClient, AurPackage},
+    cache::CachedPackage,
+    config::{AurHelper, Config, Escalation},
+    db::{conf_path, db_path},
+    db::{files::FilesDb, log::Transaction},
+    error::AppError,
+    integrity::FileIssue,
+    pacnew::PacfileScan,
+    pman::{
+        get_all_packages, get_cache, get_file_issues, get_files, get_history,
+        get_installed_packages, get_pacfiles, get_sync_files, get_update_size, get_updates,
+        is_root,
+    },
+    source::{Invocation, PackageSource, command_args},
+    structs::{event::EventCommand, package::Package, packageupdate::PackageUpdate},
+};
+
+/// The real system: reads the pacman databases and runs pacman for everything else
+#[derive(Debug, Clone)]
+pub struct PacmanSource {
+    db_path: PathBuf,
+    conf_path: PathBuf,
+    escalation: Escalation,
+    aur_helper: Option<AurHelper>,
+    aur: Option<AurClient>,
+}
+
+impl Default for PacmanSource {
+    fn default() -> Self {
+        Self::new(&Config::default())
+    }
+}
+
+impl PacmanSource {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            db_path: db_path(),
+            conf_path: conf_path(),
+            escalation: config.escalation,
+            aur_helper: config.aur_helper.clone(),
+            aur: config.aur_url().map(AurClient::new),
+        }
+    }
+
+    fn execute(&self, invocation: Invocation) -> Result<(), AppError> {
+        let Invocation {
+            mut program,
+            mut args,
+            needs_root,
+        } = invocation;
+        if needs_root
+            && !is_root()
+            && let Some(tool) = self.escalation.program()
+        {
+            args.insert(0, program);
+            program = tool.to_string();
+        }
+        let line = format!("{program} {}", args.join(" "));
+        std::io::stdout().write_all(format!("\nRunning command: {line}\n").as_bytes())?;
+
+        //stderr is shown as it comes and kept for the error
+        let mut child = Command::new(&program)
+            .args(&args)
+            .stderr(Stdio::piped())
+            .spawn()?;
+        let stderr = match child.stderr.take() {
+            Some(pipe) => forward(pipe, &mut std::io::stderr())?,
+            None => String::new(),
+        };
+        let status = child.wait()?;
+        if !status.success() {
+            return Err(AppError::CommandFailed {
+                command: line,
+                code: status.code(),
+                stderr,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Copy everything read to `to` as soon as it arrives, returns a copy of it.
+/// Prompts like `[Y/n]` end without a newline, so nothing waits for one
+fn forward(mut from: impl Read, to: &mut impl Write) -> Result<String, AppError> {
+    let mut copy = vec![];
+    let mut chunk = [0; 1024];
+    loop {
+        let n = from.read(&mut chunk)?;
+        if n == 0 {
+            break;
+        }
+        to.write_all(&chunk[..n])?;
+        to.flush()?;
+        copy.extend_from_slice(&chunk[..n]);
+    }
+    Ok(String::from_utf8_lossy(&copy).to_string())
+}
+
+impl PackageSource for PacmanSource {
+    fn installed(&self) -> Result<Vec<Package>, AppError> {
+        get_installed_packages(&self.db_path)
+    }
+
+    fn sync(&self) -> Result<Vec<Package>, AppError> {
+        get_all_packages(&self.db_path, &self.conf_path)
+    }
+
+    fn updates(&self) -> Result<Vec<PackageUpdate>, AppError> {
+        get_updates()
+    }
+
+    fn update_sizes(&self) -> Result<HashMap<String, usize>, AppError> {
+        get_update_size()
+    }
+
+    fn files(&self, pack: &Package) -> Result<Vec<String>, AppError> {
+        get_files(&self.db_path, pack)
+    }
+
+    fn check_files(&self, pack: &Package) -> Result<Vec<FileIssue>, AppError> {
+        get_file_issues(&self.db_path, Path::new("/"), pack)
+    }
+
+    fn pacfiles(&self) -> Result<PacfileScan, AppError> {
+        get_pacfiles(&self.db_path, Path::new("/"))
+    }
+
+    fn history(&self) -> Result<Vec<Transaction>, AppError> {
+        get_history(&self.conf_path)
+    }
+
+    fn cache(&self) -> Result<Vec<CachedPackage>, AppError> {
+        get_cache(&self.conf_path)
+    }
+
+    fn aur_info(&self, names: &[String]) -> Result<Vec<AurPackage>, AppError> {
+        match &self.aur {
+            Some(aur) if !names.is_empty() => aur.info(names),
+            _ => Ok(vec![]),
+        }
+    }
+
+    fn aur_search(&self, term: &str) -> Result<Vec<AurPackage>, AppError> {
+        match &self.aur {
+            Some(aur) => aur.search(term),
+            None => Err("AUR lookups are off, set AurUrl or AurHelper in the config"
+                .to_string()
+                .into()),
+        }
+    }
+
+    fn sync_files(&self) -> Result<FilesDb, AppError> {
+        get_sync_files(&self.db_path, &self.conf_path)
+    }
+
+    fn run(&self, command: &EventCommand, foreign: &HashSet<String>) -> Result<(), AppError> {
+        //stop at the first failure, later steps may depend on it
+        for invocation in command_args(command, self.aur_helper.as_ref(), foreign)? {
+            self.execute(invocation)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{sync::mpsc, time::Duration};
+
+    ///sends every write to the test
+    struct Sent(mpsc::Sender<Vec<u8>>);
+
+    impl Write for Sent {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            let _ = self.0.send(buf.to_vec());
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_forward() {
+        let (reader, mut writer) = std::io::pipe().unwrap();
+        let (tx, rx) = mpsc::channel();
+        let handle = std::thread::spawn(move || forward(reader, &mut Sent(tx)).unwrap());
+
+        //the prompt shows up while pacman still waits for an answer
+        let prompt = b":: Proceed with installation? [Y/n] ";
+        writer.write_all(prompt).unwrap();
+        let shown = rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(shown, prompt);
+
+        writer.write_all(b"\nerror: failed\n").unwrap();
+        drop(writer);
+        assert_eq!(
+            handle.join().unwrap(),
+            ":: Proceed with installation? [Y/n] \nerror: failed\n"
+        );
+    }
+}
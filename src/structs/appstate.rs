@@ -1,13 +1,29 @@
-use std::time::Duration;
+use std::{sync::Arc, time::Duration};
 
 use crate::{
-    structs::{package::Package, tab::Tab, timedstring::TimedString},
-    widgets::{installed::InstalledWidget, packages::PackagesWidget, update::UpdateWidget},
+    db::log::Transaction,
+    eventloop::EventLoop,
+    graph::PackageGraph,
+    loader::Loader,
+    source::{PackageSource, pacman::PacmanSource},
+    structs::{tab::Tab, timedstring::TimedString},
+    widgets::{
+        cache::CacheWidget, history::HistoryWidget, installed::InstalledWidget,
+        packages::PackagesWidget, pacnew::PacnewWidget, update::UpdateWidget,
+    },
 };
 
 pub struct AppState {
-    pub packages: Vec<Package>,
+    pub source: Arc<dyn PackageSource>,
+    pub events: EventLoop,
+    pub packages: Arc<PackageGraph>,
+    ///set while packages are loading in the background
+    pub loader: Option<Loader>,
     pub show_info: bool,
+    ///`pacman.log` for the info panel, read on first use and again after a reload
+    pub history: Option<Vec<Transaction>>,
+    ///`history` is being read in the background
+    pub history_loading: bool,
     pub show_help: bool,
     pub message: TimedString,
 
@@ -18,20 +34,37 @@ pub struct AppState {
     pub update_widget: UpdateWidget,
     pub packages_widget: PackagesWidget,
     pub installed_widget: InstalledWidget,
+    pub pacnew_widget: PacnewWidget,
+    pub history_widget: HistoryWidget,
+    pub cache_widget: CacheWidget,
 }
 
 impl Default for AppState {
     fn default() -> Self {
+        Self::new(Arc::new(PacmanSource::default()))
+    }
+}
+
+impl AppState {
+    pub fn new(source: Arc<dyn PackageSource>) -> Self {
         Self {
-            packages: Vec::new(),
+            source: source.clone(),
+            events: EventLoop::default(),
+            packages: Arc::default(),
+            loader: None,
             show_info: true,
+            history: None,
+            history_loading: false,
             show_help: false,
             message: TimedString::new("", Duration::from_secs(5)),
             command: String::new(),
             tab: Tab::Installed,
             update_widget: UpdateWidget::default(),
-            packages_widget: PackagesWidget::default(),
-            installed_widget: InstalledWidget::default(),
+            packages_widget: PackagesWidget::new(source.clone()),
+            installed_widget: InstalledWidget::new(source),
+            pacnew_widget: PacnewWidget::new(),
+            history_widget: HistoryWidget::new(),
+            cache_widget: CacheWidget::new(),
         }
     }
 }
@@ -0,0 +1,139 @@
+use std::{cmp::Ordering, fmt::Display};
+
+use crate::version::vercmp;
+
+/// A dependency such as `glibc>=2.38` or `sh`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Dependency {
+    pub name: String,
+    pub op: Option<DepOp>,
+    pub version: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DepOp {
+    Less,
+    LessEqual,
+    Equal,
+    GreaterEqual,
+    Greater,
+}
+
+impl From<&str> for Dependency {
+    fn from(s: &str) -> Self {
+        let Some(pos) = s.find(['<', '>', '=']) else {
+            return Dependency {
+                name: s.to_string(),
+                op: None,
+                version: None,
+            };
+        };
+        let (name, rest) = s.split_at(pos);
+        let (op, version) = if let Some(v) = rest.strip_prefix(">=") {
+            (DepOp::GreaterEqual, v)
+        } else if let Some(v) = rest.strip_prefix("<=") {
+            (DepOp::LessEqual, v)
+        } else if let Some(v) = rest.strip_prefix('>') {
+            (DepOp::Greater, v)
+        } else if let Some(v) = rest.strip_prefix('<') {
+            (DepOp::Less, v)
+        } else {
+            (DepOp::Equal, rest.trim_start_matches('='))
+        };
+        Dependency {
+            name: name.to_string(),
+            op: Some(op),
+            version: Some(version.to_string()),
+        }
+    }
+}
+
+impl Dependency {
+    /// Returns true if a package with this version fulfils the constraint.
+    /// As in pacman, if the constraint has no pkgrel (`-1`), the pkgrel of the version is ignored
+    pub fn is_satisfied_by(&self, version: &str) -> bool {
+        let (Some(op), Some(required)) = (self.op, &self.version) else {
+            return true;
+        };
+        let ord = vercmp(version, required);
+        match op {
+            DepOp::Less => ord == Ordering::Less,
+            DepOp::LessEqual => ord != Ordering::Greater,
+            DepOp::Equal => ord == Ordering::Equal,
+            DepOp::GreaterEqual => ord != Ordering::Less,
+            DepOp::Greater => ord == Ordering::Greater,
+        }
+    }
+
+    /// The constraint without the name, e.g. `>=2.38`
+    pub fn constraint(&self) -> String {
+        match (self.op, &self.version) {
+            (Some(op), Some(version)) => format!("{op}{version}"),
+            _ => String::new(),
+        }
+    }
+}
+
+impl Display for DepOp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let op = match self {
+            DepOp::Less => "<",
+            DepOp::LessEqual => "<=",
+            DepOp::Equal => "=",
+            DepOp::GreaterEqual => ">=",
+            DepOp::Greater => ">",
+        };
+        write!(f, "{op}")
+    }
+}
+
+impl Display for Dependency {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}", self.name, self.constraint())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let dep = Dependency::from("glibc>=2.38");
+        assert_eq!(dep.name, "glibc");
+        assert_eq!(dep.op, Some(DepOp::GreaterEqual));
+        assert_eq!(dep.version.as_deref(), Some("2.38"));
+        assert_eq!(dep.to_string(), "glibc>=2.38");
+
+        let dep = Dependency::from("sh");
+        assert_eq!(dep.name, "sh");
+        assert_eq!(dep.op, None);
+        assert_eq!(dep.to_string(), "sh");
+
+        let dep = Dependency::from("libfoo.so=1-64");
+        assert_eq!(dep.name, "libfoo.so");
+        assert_eq!(dep.op, Some(DepOp::Equal));
+        assert_eq!(dep.version.as_deref(), Some("1-64"));
+
+        assert_eq!(Dependency::from("a<1").op, Some(DepOp::Less));
+        assert_eq!(Dependency::from("a<=1").op, Some(DepOp::LessEqual));
+        assert_eq!(Dependency::from("a>1").op, Some(DepOp::Greater));
+    }
+
+    #[test]
+    fn test_satisfied() {
+        let dep = Dependency::from("glibc>=2.38");
+        assert!(dep.is_satisfied_by("2.39-1"));
+        assert!(dep.is_satisfied_by("2.38-3"));
+        assert!(!dep.is_satisfied_by("2.37-1"));
+
+        assert!(Dependency::from("sh").is_satisfied_by("anything"));
+        assert!(Dependency::from("foo=1.2").is_satisfied_by("1.2-4"));
+        assert!(!Dependency::from("foo=1.2-3").is_satisfied_by("1.2-4"));
+        assert!(Dependency::from("foo<2").is_satisfied_by("1.9-1"));
+        assert!(!Dependency::from("foo>1.10").is_satisfied_by("1.9-1"));
+        assert!(Dependency::from("foo>=1:1.0").is_satisfied_by("1:1.2-1"));
+        assert!(!Dependency::from("foo>=1:1.0").is_satisfied_by("2.0-1"));
+        assert!(!Dependency::from("foo>=2.0").is_satisfied_by("2.0rc1-1"));
+    }
+}
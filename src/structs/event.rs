@@ -1,3 +1,18 @@
+use std::path::PathBuf;
+
+use ratatui::crossterm::event::KeyEvent;
+
+use crate::{loader::LoadEvent, plan::RemoveMode, structs::package::Package};
+
+/// Everything the main loop reacts to, each one is followed by a redraw
+#[derive(Debug)]
+pub enum AppEvent {
+    Key(KeyEvent),
+    Resize,
+    Tick,
+    Load(LoadEvent),
+}
+
 #[derive(PartialEq)]
 pub enum EventResult {
     None,
@@ -5,13 +20,48 @@ pub enum EventResult {
     Command(EventCommand),
     NeedsUpdate,
     GotoInstalled(String),
+    GotoPackage(String),
+    Message(String),
+    ///slow work for the main loop to run on a thread, the result comes back as a `LoadEvent`
+    Background(BackgroundJob),
 }
 
-#[derive(PartialEq)]
+/// Work too slow for the ui thread
+#[derive(Debug, Clone, PartialEq)]
+pub enum BackgroundJob {
+    ///read the repository `.files` databases
+    FilesDb,
+    ///check the installed files of packages, hashing every one of them
+    CheckFiles(Vec<Package>),
+    ///search the file lists of the installed packages for these paths
+    FindOwners(Vec<PathBuf>),
+    ///look for `.pacnew` and `.pacsave` files
+    Pacfiles,
+    ///read `pacman.log`
+    History,
+    ///scan the package caches
+    Cache,
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum EventCommand {
-    RemoveSelected(Vec<String>),
+    RemoveSelected(Vec<String>, RemoveMode),
     InstallOrUpdateSelected(Vec<String>),
     QuerySelected(Vec<String>),
     SyncDatabase,
     SyncAndUpdateAll,
+    ///move a `.pacnew`/`.pacsave` over the live file
+    ReplaceWithPacfile(PathBuf, PathBuf),
+    DeletePacfile(PathBuf),
+    ///`pacman -U` a package file, such as an older version from the cache
+    InstallFile(PathBuf),
+    ///remove package files from the cache, with their signatures
+    DeleteCached(Vec<PathBuf>),
+}
+
+impl EventCommand {
+    /// Whether the command changes the system, so has to run as root
+    pub fn needs_root(&self) -> bool {
+        !matches!(self, EventCommand::QuerySelected(_))
+    }
 }
@@ -1,5 +1,6 @@
 pub mod appstate;
 pub mod changetype;
+pub mod dependency;
 pub mod event;
 pub mod focus;
 pub mod package;
@@ -1,18 +1,35 @@
-use crate::{structs::reason::Reason, version::ChangeType};
+use crate::{
+    aur::AurPackage,
+    structs::{dependency::Dependency, reason::Reason},
+    version::ChangeType,
+};
 
 #[derive(Debug, Default, Clone, PartialEq)]
 pub struct Package {
     pub name: String,
     pub required_by: Vec<String>,
     pub required_by_optional: Vec<String>,
-    pub dependencies: Vec<String>,
+    pub dependencies: Vec<Dependency>,
     pub dependencies_optional: Vec<String>,
-    pub provides: Option<Vec<String>>,
+    pub provides: Vec<Dependency>, //virtual names such as `sh` or `libfoo.so=1-64`
+    pub conflicts: Vec<Dependency>,
+    pub replaces: Vec<Dependency>,
+    pub files: Option<Vec<String>>,
     pub reason: Reason,
     //info
     pub version: String,
     pub description: String,
     pub validated: bool,
+    pub base: String,
+    pub url: String,
+    pub architecture: String,
+    pub packager: String,
+    pub licenses: Vec<String>,
+    pub groups: Vec<String>,
+    pub build_date: Option<String>,
+    pub installed_size: usize,
+    pub download_size: usize,
+    pub repository: Option<String>, //sync repository such as core or extra
 
     //installed for installed packages
     pub installed: Option<String>,
@@ -22,6 +39,50 @@ pub struct Package {
     pub new_version_size: Option<usize>,
     pub change_type: Option<ChangeType>,
 
+    //aur details for foreign packages, when AUR lookups are on
+    pub aur: Option<AurPackage>,
+
     //full recursive dependency list
     pub dependencies_count: usize,
 }
+
+impl Package {
+    /// Returns true if this package fulfils the dependency, either by name or through provides.
+    /// As in pacman, a provide without a version cannot satisfy a versioned dependency
+    pub fn satisfies(&self, dep: &Dependency) -> bool {
+        if self.name == dep.name && dep.is_satisfied_by(&self.version) {
+            return true;
+        }
+        self.provides.iter().any(|prov| {
+            prov.name == dep.name
+                && match &prov.version {
+                    Some(version) => dep.is_satisfied_by(version),
+                    None => dep.op.is_none(),
+                }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_satisfies() {
+        let readline = Package {
+            name: "readline".to_string(),
+            version: "8.2.010-1".to_string(),
+            provides: vec![
+                Dependency::from("libreadline.so=8-64"),
+                Dependency::from("rl"),
+            ],
+            ..Default::default()
+        };
+        assert!(readline.satisfies(&Dependency::from("readline>=8")));
+        assert!(readline.satisfies(&Dependency::from("libreadline.so=8-64")));
+        assert!(!readline.satisfies(&Dependency::from("libreadline.so=9-64")));
+        assert!(readline.satisfies(&Dependency::from("rl")));
+        assert!(!readline.satisfies(&Dependency::from("rl>=1")));
+        assert!(!readline.satisfies(&Dependency::from("ncurses")));
+    }
+}
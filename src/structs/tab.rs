@@ -6,6 +6,9 @@ pub enum Tab {
     Installed,
     Packages,
     Updates,
+    Pacnew,
+    History,
+    Cache,
 }
 impl Tab {
     pub fn values() -> Vec<String> {
@@ -13,6 +16,9 @@ impl Tab {
             Tab::Installed.to_string(),
             Tab::Packages.to_string(),
             Tab::Updates.to_string(),
+            Tab::Pacnew.to_string(),
+            Tab::History.to_string(),
+            Tab::Cache.to_string(),
         ]
     }
 
@@ -20,14 +26,20 @@ impl Tab {
         *self = match self {
             Tab::Installed => Tab::Packages,
             Tab::Packages => Tab::Updates,
-            Tab::Updates => Tab::Installed,
+            Tab::Updates => Tab::Pacnew,
+            Tab::Pacnew => Tab::History,
+            Tab::History => Tab::Cache,
+            Tab::Cache => Tab::Installed,
         };
     }
     pub fn cycle_prev(&mut self) {
         *self = match self {
-            Tab::Installed => Tab::Updates,
+            Tab::Installed => Tab::Cache,
             Tab::Packages => Tab::Installed,
             Tab::Updates => Tab::Packages,
+            Tab::Pacnew => Tab::Updates,
+            Tab::History => Tab::Pacnew,
+            Tab::Cache => Tab::History,
         };
     }
 }
@@ -38,6 +50,9 @@ impl Display for Tab {
             Tab::Installed => write!(f, "Installed"),
             Tab::Packages => write!(f, "Packages"),
             Tab::Updates => write!(f, "Updates"),
+            Tab::Pacnew => write!(f, "Pacnew"),
+            Tab::History => write!(f, "History"),
+            Tab::Cache => write!(f, "Cache"),
         }
     }
 }
@@ -49,6 +64,9 @@ impl From<&Tab> for Option<usize> {
             Tab::Installed => Some(0),
             Tab::Packages => Some(1),
             Tab::Updates => Some(2),
+            Tab::Pacnew => Some(3),
+            Tab::History => Some(4),
+            Tab::Cache => Some(5),
         }
     }
 }
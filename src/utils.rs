@@ -16,6 +16,40 @@ pub fn thousands(size: usize) -> String {
     }
     str
 }
+/// Format a size in bytes as KiB, MiB or GiB
+pub fn human_size(size: usize) -> String {
+    let units = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut size = size as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < units.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{size} {}", units[unit])
+    } else {
+        format!("{size:.1} {}", units[unit])
+    }
+}
+
+/// Time since a local `YYYY-MM-DD HH:MM[:SS]` date, as `3 days`
+pub fn age(date: &str) -> Option<String> {
+    let time = date
+        .parse::<jiff::civil::DateTime>()
+        .ok()?
+        .to_zoned(jiff::tz::TimeZone::system())
+        .ok()?;
+    let days = jiff::Timestamp::now()
+        .duration_since(time.timestamp())
+        .as_secs()
+        / 86400;
+    Some(match days {
+        ..1 => "less than a day".to_string(),
+        1 => "1 day".to_string(),
+        d => format!("{d} days"),
+    })
+}
+
 /// Natural sort comparison of two strings
 /// So that "file2" < "file10"
 /// Split into number and string tokens
@@ -44,11 +78,7 @@ enum Token {
 fn get_tokens(s: &str) -> Vec<Token> {
     let mut tokens = vec![];
     let mut it = s.chars().peekable();
-    loop {
-        let Some(c) = it.next() else {
-            break;
-        };
-
+    while let Some(c) = it.next() {
         if c.is_numeric() {
             let mut s = String::from(c);
             while let Some(n) = it.peek() {
@@ -102,4 +132,23 @@ mod tests {
         assert_eq!(natural_cmp("101235555", "10406325"), Ordering::Greater);
         assert_eq!(natural_cmp("101235555", "8219"), Ordering::Greater);
     }
+
+    #[test]
+    fn test_human_size() {
+        assert_eq!(human_size(0), "0 B");
+        assert_eq!(human_size(1023), "1023 B");
+        assert_eq!(human_size(1536), "1.5 KiB");
+        assert_eq!(human_size(9453056), "9.0 MiB");
+    }
+
+    #[test]
+    fn test_age() {
+        assert!(age("2000-01-01 10:00:00").is_some_and(|a| a.ends_with(" days")));
+        assert!(age("2000-01-01 10:00").is_some());
+        assert_eq!(
+            age("2999-01-01 10:00:00").as_deref(),
+            Some("less than a day")
+        );
+        assert_eq!(age("yesterday"), None);
+    }
 }
@@ -1,7 +1,8 @@
-use std::fmt::Display;
+use std::{cmp::Ordering, fmt::Display};
 
 ///This holds the epoch, version, and pkgver
-#[derive(Debug, PartialEq, Eq, Clone)]
+///Ordering follows pacman's `vercmp`
+#[derive(Debug, Clone)]
 pub struct Version {
     pub raw: String,
     pub epoch: u32,
@@ -15,22 +16,9 @@ pub struct Version {
 impl From<&str> for Version {
     fn from(s: &str) -> Self {
         let raw = s.to_string();
-        let mut parts = s.split(':');
-        let (epoch, rest) = if let Some(epoch_str) = parts.next() {
-            if let Ok(epoch) = epoch_str.parse::<u32>() {
-                (epoch, parts.next().unwrap_or(""))
-            } else {
-                (0, s)
-            }
-        } else {
-            (0, s)
-        };
-
-        let (version_str, pkgver) = if let Some((ver, pkgver)) = rest.rsplit_once('-') {
-            (ver, pkgver.parse().unwrap_or(0))
-        } else {
-            (rest, 0)
-        };
+        let (epoch, version_str, pkgver) = parse_evr(s);
+        let epoch = epoch.parse().unwrap_or(0);
+        let pkgver = pkgver.and_then(|p| p.parse().ok()).unwrap_or(0);
 
         let parts: Vec<&str> = version_str.splitn(4, '.').collect();
         let mut major = "".to_string();
@@ -62,7 +50,9 @@ impl From<&str> for Version {
 
 impl Version {
     pub fn change_type(&self, other: &Version) -> ChangeType {
-        if self.epoch != other.epoch {
+        if other < self {
+            ChangeType::Downgrade
+        } else if self.epoch != other.epoch {
             ChangeType::Epoch
         } else if self.major != other.major {
             ChangeType::Major
@@ -80,15 +70,246 @@ impl Version {
 
 #[derive(Debug, Clone, PartialEq, PartialOrd, Eq, Hash, Ord)]
 pub enum ChangeType {
-    Pkgver,   //build change only
-    Revision, //smaller than patch change, anything after 3rd dot
-    Patch,    //third dot change
-    Minor,    //second dot change
-    Major,    //first dot change
-    Epoch,    //change to force update, even if it may look smaller
+    Pkgver,    //build change only
+    Revision,  //smaller than patch change, anything after 3rd dot
+    Patch,     //third dot change
+    Minor,     //second dot change
+    Major,     //first dot change
+    Epoch,     //change to force update, even if it may look smaller
+    Downgrade, //the new version is older than the installed one
+}
+impl Ord for Version {
+    fn cmp(&self, other: &Self) -> Ordering {
+        vercmp(&self.raw, &other.raw)
+    }
+}
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl PartialEq for Version {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+impl Eq for Version {}
+
+/// Split `epoch:version-pkgrel` like libalpm's parseEVR.
+/// A missing epoch is "0", a missing pkgrel is None
+fn parse_evr(evr: &str) -> (&str, &str, Option<&str>) {
+    let digits = evr.len() - evr.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    let (epoch, rest) = match evr[digits..].strip_prefix(':') {
+        Some(rest) if digits > 0 => (&evr[..digits], rest),
+        Some(rest) => ("0", rest),
+        None => ("0", evr),
+    };
+    match rest.rsplit_once('-') {
+        Some((version, pkgrel)) => (epoch, version, Some(pkgrel)),
+        None => (epoch, rest, None),
+    }
+}
+
+/// Compare two full package versions like `alpm_pkg_vercmp`.
+/// The pkgrel is only compared if both versions have one
+pub fn vercmp(a: &str, b: &str) -> Ordering {
+    if a == b {
+        return Ordering::Equal;
+    }
+    let (epoch_a, version_a, rel_a) = parse_evr(a);
+    let (epoch_b, version_b, rel_b) = parse_evr(b);
+    rpmvercmp(epoch_a, epoch_b)
+        .then_with(|| rpmvercmp(version_a, version_b))
+        .then_with(|| match (rel_a, rel_b) {
+            (Some(rel_a), Some(rel_b)) => rpmvercmp(rel_a, rel_b),
+            _ => Ordering::Equal,
+        })
+}
+
+/// Compare version segments the way rpm and pacman do:
+/// alphanumeric blocks are compared one by one, numbers numerically and letters lexically.
+/// Numbers are newer than letters, and trailing letters mean a pre-release: `1.0rc1` < `1.0` < `1.0.1`
+fn rpmvercmp(a: &str, b: &str) -> Ordering {
+    if a == b {
+        return Ordering::Equal;
+    }
+    let a = a.as_bytes();
+    let b = b.as_bytes();
+    let (mut one, mut two) = (0, 0);
+
+    while one < a.len() && two < b.len() {
+        //skip separators
+        let sep_one = one;
+        let sep_two = two;
+        while one < a.len() && !a[one].is_ascii_alphanumeric() {
+            one += 1;
+        }
+        while two < b.len() && !b[two].is_ascii_alphanumeric() {
+            two += 1;
+        }
+        //if we ran to the end of either, we are finished with the loop
+        if one >= a.len() || two >= b.len() {
+            break;
+        }
+        //if the separator lengths were different, we are also finished
+        if one - sep_one != two - sep_two {
+            return (one - sep_one).cmp(&(two - sep_two));
+        }
+
+        //grab the next block of the same type from both
+        let is_num = a[one].is_ascii_digit();
+        let same_type = |c: &u8| {
+            if is_num {
+                c.is_ascii_digit()
+            } else {
+                c.is_ascii_alphabetic()
+            }
+        };
+        let end_one = one + a[one..].iter().take_while(|c| same_type(c)).count();
+        let end_two = two + b[two..].iter().take_while(|c| same_type(c)).count();
+        let mut block_one = &a[one..end_one];
+        let mut block_two = &b[two..end_two];
+
+        //different types: numeric blocks are newer than alpha blocks
+        if block_two.is_empty() {
+            return if is_num {
+                Ordering::Greater
+            } else {
+                Ordering::Less
+            };
+        }
+
+        if is_num {
+            //leading zeros do not count, then the longer number is bigger
+            while block_one.first() == Some(&b'0') {
+                block_one = &block_one[1..];
+            }
+            while block_two.first() == Some(&b'0') {
+                block_two = &block_two[1..];
+            }
+            let len = block_one.len().cmp(&block_two.len());
+            if len != Ordering::Equal {
+                return len;
+            }
+        }
+        let ord = block_one.cmp(block_two);
+        if ord != Ordering::Equal {
+            return ord;
+        }
+
+        one = end_one;
+        two = end_two;
+    }
+
+    //this catches the case where all segments compared identically but one has leftovers
+    let rest_one = a.get(one);
+    let rest_two = b.get(two);
+    match (rest_one, rest_two) {
+        (None, None) => Ordering::Equal,
+        //the one with leftover alpha is older (1.0rc < 1.0), leftover numbers are newer (1.0 < 1.0.1)
+        (None, Some(c)) if !c.is_ascii_alphabetic() => Ordering::Less,
+        (Some(c), _) if c.is_ascii_alphabetic() => Ordering::Less,
+        _ => Ordering::Greater,
+    }
 }
+
 impl Display for ChangeType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{:?}", self)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vercmp() {
+        use Ordering::*;
+        let table = [
+            //same
+            ("1.5.0", "1.5.0", Equal),
+            ("1.5.1", "1.5.0", Greater),
+            //mixed length
+            ("1.5.1", "1.5", Greater),
+            ("1.0", "1.0.0", Less),
+            ("1.2.3.4.5", "1.2.3.4.10", Less),
+            ("1.2.3.4.5", "1.2.3.4", Greater),
+            //with pkgrel, simple
+            ("1.5.0-1", "1.5.0-1", Equal),
+            ("1.5.0-1", "1.5.0-2", Less),
+            ("1.5.0-1", "1.5.1-1", Less),
+            ("1.5.0-2", "1.5.1-1", Less),
+            //with pkgrel, mixed lengths
+            ("1.5-1", "1.5.1-1", Less),
+            ("1.5-2", "1.5.1-1", Less),
+            ("1.5-2", "1.5.1-2", Less),
+            //mixed pkgrel inclusion, pkgrel is ignored
+            ("1.5", "1.5-1", Equal),
+            ("1.5-1", "1.5", Equal),
+            ("1.1-1", "1.1", Equal),
+            ("1.0-1", "1.1", Less),
+            ("1.1-1", "1.0", Greater),
+            //alphanumerics
+            ("1.0a", "1.0alpha", Less),
+            ("1.0alpha", "1.0b", Less),
+            ("1.0b", "1.0beta", Less),
+            ("1.0beta", "1.0rc", Less),
+            ("1.0rc", "1.0", Less),
+            ("2.0rc1", "2.0", Less),
+            ("2.0rc1", "2.0rc2", Less),
+            //alpha dots
+            ("1.5.a", "1.5", Greater),
+            ("1.5.b", "1.5.a", Greater),
+            ("1.5.1", "1.5.b", Greater),
+            //alpha dots and dashes
+            ("1.5.b-1", "1.5.b", Equal),
+            ("1.5-1", "1.5.b", Less),
+            //same or similar content, differing separators
+            ("2.0", "2_0", Equal),
+            ("2.0_a", "2_0.a", Equal),
+            ("2.0a", "2.0.a", Less),
+            ("2___a", "2_a", Greater),
+            //epoch
+            ("0:1.0", "0:1.0", Equal),
+            ("0:1.0", "0:1.1", Less),
+            ("1:1.0", "0:1.0", Greater),
+            ("1:1.0", "0:1.1", Greater),
+            ("1:1.0", "2:1.1", Less),
+            ("1:1.0", "0:1.0-1", Greater),
+            ("1:1.0-1", "0:1.1-1", Greater),
+            ("0:1.0", "1.0", Equal),
+            ("0:1.0", "1.1", Less),
+            ("1:1.0", "1.1", Greater),
+            ("1:2.0rc1-3", "2.0-1", Greater),
+            ("1:2.0rc1-3", "1:2.0-1", Less),
+            //dates and git revisions
+            ("20240101", "20231231", Greater),
+            ("20240101-1", "20240101-2", Less),
+            ("r123.abcdef", "r124.aaaaaa", Less),
+            ("r123.abcdef", "r123.abcdeg", Less),
+            ("r1000.abc", "r999.abc", Greater),
+            //leading zeros
+            ("1.01", "1.1", Equal),
+            ("1.001", "1.0001", Equal),
+        ];
+        for (a, b, expected) in table {
+            assert_eq!(vercmp(a, b), expected, "{a} vs {b}");
+            assert_eq!(vercmp(b, a), expected.reverse(), "{b} vs {a}");
+        }
+    }
+
+    #[test]
+    fn test_change_type() {
+        let change = |a: &str, b: &str| Version::from(a).change_type(&Version::from(b));
+        assert_eq!(change("1.2.3-1", "1.2.3-2"), ChangeType::Pkgver);
+        assert_eq!(change("1.2.3.4-1", "1.2.3.5-1"), ChangeType::Revision);
+        assert_eq!(change("1.2.3-1", "1.2.4-1"), ChangeType::Patch);
+        assert_eq!(change("1.2.3-1", "1.3.0-1"), ChangeType::Minor);
+        assert_eq!(change("1.2.3-1", "2.0-1"), ChangeType::Major);
+        assert_eq!(change("1.2.3-1", "1:1.0-1"), ChangeType::Epoch);
+        assert_eq!(change("20240101-1", "20240201-1"), ChangeType::Major);
+        assert_eq!(change("1.2.3-2", "1.2.3-1"), ChangeType::Downgrade);
+        assert_eq!(change("1:1.0-1", "2.0-1"), ChangeType::Downgrade);
+    }
+}
@@ -0,0 +1,391 @@
+use ratatui::{
+    crossterm::event::{KeyCode, KeyEvent},
+    layout::{Constraint, Layout, Rect},
+    style::Color,
+    widgets::Widget,
+};
+
+use std::{cmp::Ordering, collections::HashSet, sync::Arc};
+
+use crate::{
+    cache::{CachedPackage, CleanPolicy, with_signatures},
+    error::AppError,
+    graph::PackageGraph,
+    structs::{
+        event::{BackgroundJob, EventCommand, EventResult},
+        package::Package,
+    },
+    utils::human_size,
+    version::vercmp,
+    widgets::{
+        Commands, CurrentPackage,
+        cleandialog::CleanDialog,
+        confirmdialog::ConfirmDialog,
+        table::{Sort, TableFocus, TableRow, TableWidget},
+    },
+};
+
+/// Package files in the pacman caches, grouped by package with every cached version.
+/// The cache directories are read on first open and again after each reload
+#[derive(Debug, Clone)]
+pub struct CacheWidget {
+    graph: Arc<PackageGraph>,
+    cached: Vec<CachedPackage>,
+    ///the tab was opened, a reload reads the cache directories again
+    scanned: bool,
+    list: TableWidget,
+    versions: TableWidget,
+    versions_focused: bool,
+    confirm: Option<ConfirmDialog>,
+    clean_dialog: Option<CleanDialog>,
+}
+
+impl Default for CacheWidget {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CacheWidget {
+    pub fn new() -> Self {
+        Self {
+            graph: Arc::default(),
+            cached: vec![],
+            scanned: false,
+            list: TableWidget::new(
+                &["Package", "Versions", "Installed", "Size"],
+                vec![
+                    Constraint::Percentage(40),
+                    Constraint::Length(8),
+                    Constraint::Percentage(30),
+                    Constraint::Length(10),
+                ],
+            ),
+            versions: TableWidget::new(
+                &["Version", "Arch", "Size", "Status", "File"],
+                vec![
+                    Constraint::Percentage(20),
+                    Constraint::Length(7),
+                    Constraint::Length(10),
+                    Constraint::Length(9),
+                    Constraint::Percentage(60),
+                ],
+            )
+            .with_sort(0, Sort::None)
+            .with_no_focus(),
+            versions_focused: false,
+            confirm: None,
+            clean_dialog: None,
+        }
+    }
+
+    /// Returns the job scanning the caches again once the tab was opened
+    pub fn set_data(&mut self, graph: Arc<PackageGraph>) -> Option<BackgroundJob> {
+        self.graph = graph;
+        self.scanned.then(|| self.refresh())
+    }
+
+    /// Scan the caches again
+    pub fn refresh(&mut self) -> BackgroundJob {
+        self.scanned = true;
+        self.list.set_title("Reading the package cache…");
+        BackgroundJob::Cache
+    }
+
+    /// Show the cached packages read in the background
+    pub fn set_cache(&mut self, res: Result<Vec<CachedPackage>, AppError>) {
+        match res {
+            Ok(cached) => {
+                self.cached = cached;
+                self.update_rows();
+            }
+            Err(e) => {
+                self.cached.clear();
+                self.update_rows();
+                self.list
+                    .set_title(&format!("Error reading package cache: {e}"));
+            }
+        }
+    }
+
+    ///installed version of a package, if it is installed
+    fn installed_version(&self, name: &str) -> Option<&str> {
+        self.graph
+            .get(name)
+            .filter(|p| p.installed.is_some())
+            .map(|p| p.version.as_str())
+    }
+
+    fn update_rows(&mut self) {
+        let mut rows: Vec<TableRow> = vec![];
+        let mut names: Vec<&str> = self.cached.iter().map(|c| c.name.as_str()).collect();
+        names.dedup();
+        for name in names {
+            let files: Vec<&CachedPackage> =
+                self.cached.iter().filter(|c| c.name == name).collect();
+            let installed = self.installed_version(name);
+            let size: u64 = files.iter().map(|c| c.size).sum();
+            rows.push(
+                TableRow::new(vec![
+                    name.to_string(),
+                    files.len().to_string(),
+                    installed.unwrap_or_default().to_string(),
+                    human_size(size as usize),
+                ])
+                .with_highlight(installed.is_none().then_some(Color::DarkGray)),
+            );
+        }
+        self.list.set_data(rows);
+        let total: u64 = self.cached.iter().map(|c| c.size).sum();
+        self.list.set_title(&format!(
+            "{} cached files of {} packages, {}",
+            self.cached.len(),
+            self.list.rows().len(),
+            human_size(total as usize)
+        ));
+        self.update_versions();
+    }
+
+    fn current_name(&self) -> Option<&str> {
+        self.list.get_current().map(|r| r.cells[0].as_str())
+    }
+
+    fn update_versions(&mut self) {
+        let Some(name) = self.current_name() else {
+            self.versions.set_title("Versions");
+            self.versions.set_data(vec![]);
+            return;
+        };
+        let installed = self.installed_version(name);
+        //newest first
+        let rows = self
+            .cached
+            .iter()
+            .rev()
+            .filter(|c| c.name == name)
+            .map(|c| {
+                let (status, color) = match installed.map(|v| vercmp(&c.version, v)) {
+                    Some(Ordering::Equal) => ("installed", Some(Color::Green)),
+                    Some(Ordering::Less) => ("older", None),
+                    Some(Ordering::Greater) => ("newer", Some(Color::Cyan)),
+                    None => ("", Some(Color::DarkGray)),
+                };
+                TableRow::new(vec![
+                    c.version.clone(),
+                    c.arch.clone(),
+                    human_size(c.size as usize),
+                    status.to_string(),
+                    c.path.to_string_lossy().to_string(),
+                ])
+                .with_highlight(color)
+            })
+            .collect();
+        let title = format!("Versions of {name}");
+        self.versions.set_title(&title);
+        self.versions.set_data(rows);
+    }
+
+    fn current_file(&self) -> Option<&CachedPackage> {
+        let row = self.versions.get_current()?;
+        self.cached
+            .iter()
+            .find(|c| c.path.to_string_lossy() == row.cells[4])
+    }
+
+    fn set_versions_focus(&mut self, focused: bool) {
+        self.versions_focused = focused;
+        if focused {
+            self.list.focus(TableFocus::UnfocusedDimmed);
+            self.versions.focus(TableFocus::Focused);
+        } else {
+            self.list.focus(TableFocus::Focused);
+            self.versions.focus(TableFocus::Unfocused);
+        }
+    }
+
+    ///ask before installing the chosen version
+    fn confirm_install(&mut self) {
+        let Some(file) = self.current_file() else {
+            return;
+        };
+        let question = match self.installed_version(&file.name) {
+            Some(v) => match vercmp(&file.version, v) {
+                Ordering::Less => format!("Downgrade {} from {v} to {}?", file.name, file.version),
+                Ordering::Equal => format!("Reinstall {} {}?", file.name, file.version),
+                Ordering::Greater => format!("Upgrade {} from {v} to {}?", file.name, file.version),
+            },
+            None => format!("Install {} {}?", file.name, file.version),
+        };
+        self.confirm = Some(ConfirmDialog::new(
+            EventCommand::InstallFile(file.path.clone()),
+            question,
+        ));
+    }
+
+    fn open_clean_dialog(&mut self) {
+        let installed: HashSet<String> = self
+            .cached
+            .iter()
+            .filter(|c| self.installed_version(&c.name).is_some())
+            .map(|c| c.name.clone())
+            .collect();
+        self.clean_dialog = Some(CleanDialog::new(self.cached.clone(), installed));
+    }
+
+    ///ask before deleting what the policy chose
+    fn confirm_clean(&mut self, policy: CleanPolicy) -> EventResult {
+        let files = policy.files(&self.cached, |n| self.installed_version(n).is_some());
+        if files.is_empty() {
+            return EventResult::Message("Nothing to clean".to_string());
+        }
+        let size: u64 = files.iter().map(|c| c.size).sum();
+        let question = format!(
+            "Delete {} cached files, freeing {}?",
+            files.len(),
+            human_size(size as usize)
+        );
+        self.confirm = Some(ConfirmDialog::new(
+            EventCommand::DeleteCached(with_signatures(&files)),
+            question,
+        ));
+        EventResult::None
+    }
+}
+
+impl Widget for CacheWidget {
+    fn render(self, area: Rect, buf: &mut ratatui::buffer::Buffer) {
+        let areas = Layout::horizontal([Constraint::Percentage(45), Constraint::Percentage(55)])
+            .split(area);
+        self.list.render(areas[0], buf);
+        self.versions.render(areas[1], buf);
+        if let Some(dialog) = self.clean_dialog {
+            dialog.render(area, buf);
+        }
+        if let Some(dialog) = self.confirm {
+            dialog.render(area, buf);
+        }
+    }
+}
+
+impl Commands for CacheWidget {
+    fn command_descriptions(&self) -> Vec<(&str, &str, &str)> {
+        vec![
+            ("U", "Install the chosen cached version", "Install"),
+            ("c", "Clean the cache by a policy", "Clean"),
+            ("Enter", "Go to package", "Package"),
+            ("←/h →/l", "Focus packages or versions", ""),
+        ]
+    }
+
+    fn handle_key_event(&mut self, key: &KeyEvent) -> Option<EventResult> {
+        //confirmation takes all keys while open
+        if let Some(dialog) = &self.confirm {
+            let Some(res) = dialog.handle_key_event(key) else {
+                return Some(EventResult::None);
+            };
+            self.confirm = None;
+            return Some(res);
+        }
+        //dialog takes all keys while open
+        if let Some(dialog) = self.clean_dialog.as_mut() {
+            let Some(res) = dialog.handle_key_event(key) else {
+                return Some(EventResult::None);
+            };
+            self.clean_dialog = None;
+            return Some(match res {
+                Some(policy) => self.confirm_clean(policy),
+                None => EventResult::None,
+            });
+        }
+        if self.versions_focused {
+            if self.versions.handle_key_event(key) {
+                return Some(EventResult::None);
+            }
+        } else if self.list.handle_key_event(key) {
+            self.update_versions();
+            return Some(EventResult::None);
+        }
+
+        match key.code {
+            KeyCode::Left | KeyCode::Char('h') => self.set_versions_focus(false),
+            KeyCode::Right | KeyCode::Char('l') => self.set_versions_focus(true),
+            KeyCode::Char('U') => self.confirm_install(),
+            KeyCode::Char('c') => self.open_clean_dialog(),
+            KeyCode::Enter => {
+                let name = self.current_name()?.to_string();
+                return Some(match self.installed_version(&name) {
+                    Some(_) => EventResult::GotoInstalled(name),
+                    None if self.graph.get(&name).is_some() => EventResult::GotoPackage(name),
+                    None => EventResult::Message(format!("{name} is not in any repository")),
+                });
+            }
+            _ => return None,
+        }
+        Some(EventResult::None)
+    }
+}
+
+impl CurrentPackage for CacheWidget {
+    fn current_package(&self) -> Option<&Package> {
+        self.current_name().and_then(|n| self.graph.get(n))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        pman::load_packages,
+        source::{PackageSource, fixture::FixtureSource},
+    };
+    use std::path::Path;
+
+    ///the cache tab of the fixture system, with what it read
+    fn fixture_widget() -> (Vec<CachedPackage>, CacheWidget) {
+        let source = FixtureSource::new(&Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures"));
+        let graph = load_packages(&source, &|_| {}, &|_| {}).unwrap();
+        let mut widget = CacheWidget::new();
+        //nothing is read until the tab is opened
+        assert!(widget.set_data(Arc::new(graph)).is_none());
+        assert!(widget.refresh() == BackgroundJob::Cache);
+        let cached = source.cache().unwrap();
+        widget.set_cache(Ok(cached.clone()));
+        (cached, widget)
+    }
+
+    #[test]
+    fn test_install_cached() {
+        let (cached, mut widget) = fixture_widget();
+        //the older bash is offered
+        assert_eq!(
+            widget.current_package().map(|p| p.name.as_str()),
+            Some("bash")
+        );
+        let key = |c| KeyEvent::from(KeyCode::Char(c));
+        widget.handle_key_event(&key('l'));
+        widget.handle_key_event(&key('j'));
+        widget.handle_key_event(&key('U'));
+        let res = widget.handle_key_event(&key('y'));
+        let path = cached[0].path.clone();
+        assert!(res == Some(EventResult::Command(EventCommand::InstallFile(path))));
+    }
+
+    #[test]
+    fn test_clean_cached() {
+        let (_, mut widget) = fixture_widget();
+        //cleaning uninstalled packages only removes nano
+        widget.handle_key_event(&KeyEvent::from(KeyCode::Char('c')));
+        widget.handle_key_event(&KeyEvent::from(KeyCode::Tab));
+        widget.handle_key_event(&KeyEvent::from(KeyCode::Enter));
+        let res = widget.handle_key_event(&KeyEvent::from(KeyCode::Char('y')));
+        let Some(EventResult::Command(EventCommand::DeleteCached(paths))) = res else {
+            panic!("expected a cleanup");
+        };
+        assert_eq!(paths.len(), 2);
+        assert!(
+            paths
+                .iter()
+                .all(|p| p.to_string_lossy().contains("nano-7.2"))
+        );
+    }
+}
@@ -0,0 +1,158 @@
+use ratatui::{
+    crossterm::event::{KeyCode, KeyEvent},
+    layout::Rect,
+    style::{Color, Stylize},
+    text::{Line, Span},
+    widgets::Widget,
+};
+
+use std::collections::HashSet;
+
+use crate::{
+    cache::{CachedPackage, CleanPolicy},
+    utils::human_size,
+    widgets::{datedialog::valid_date, render_dialog},
+};
+
+///files listed in the preview before the rest are only counted
+const PREVIEW_FILES: usize = 8;
+
+/// Choose which cached files to delete, with a preview of what would go
+#[derive(Debug, Clone)]
+pub struct CleanDialog {
+    cached: Vec<CachedPackage>,
+    installed: HashSet<String>,
+    ///0 keep last, 1 uninstalled, 2 older than
+    choice: usize,
+    keep: String,
+    date: String,
+    error: Option<String>,
+}
+
+impl CleanDialog {
+    pub fn new(cached: Vec<CachedPackage>, installed: HashSet<String>) -> Self {
+        Self {
+            cached,
+            installed,
+            choice: 0,
+            keep: "3".to_string(),
+            date: String::new(),
+            error: None,
+        }
+    }
+
+    ///the chosen policy, if its value is valid
+    fn policy(&self) -> Result<CleanPolicy, String> {
+        match self.choice {
+            0 => self
+                .keep
+                .parse()
+                .map(CleanPolicy::KeepLast)
+                .map_err(|_| "Number of versions to keep is needed".to_string()),
+            1 => Ok(CleanPolicy::Uninstalled),
+            _ if self.date.is_empty() || !valid_date(&self.date) => {
+                Err("Dates are YYYY, YYYY-MM or YYYY-MM-DD".to_string())
+            }
+            _ => Ok(CleanPolicy::OlderThan(self.date.clone())),
+        }
+    }
+
+    /// The files the chosen policy would delete
+    pub fn files(&self) -> Vec<&CachedPackage> {
+        match self.policy() {
+            Ok(policy) => policy.files(&self.cached, |n| self.installed.contains(n)),
+            Err(_) => vec![],
+        }
+    }
+
+    /// Returns Some when the dialog should close, with the policy if confirmed
+    pub fn handle_key_event(&mut self, key: &KeyEvent) -> Option<Option<CleanPolicy>> {
+        match key.code {
+            KeyCode::Esc => return Some(None),
+            KeyCode::Enter => match self.policy() {
+                Ok(policy) => return Some(Some(policy)),
+                Err(e) => self.error = Some(e),
+            },
+            KeyCode::Tab | KeyCode::Down | KeyCode::Char('j') => {
+                self.choice = (self.choice + 1) % 3
+            }
+            KeyCode::BackTab | KeyCode::Up | KeyCode::Char('k') => {
+                self.choice = (self.choice + 2) % 3
+            }
+            KeyCode::Backspace => {
+                match self.choice {
+                    0 => self.keep.pop(),
+                    2 => self.date.pop(),
+                    _ => None,
+                };
+            }
+            KeyCode::Char(c) if c.is_ascii_digit() && self.choice == 0 => self.keep.push(c),
+            KeyCode::Char(c) if (c.is_ascii_digit() || c == '-') && self.choice == 2 => {
+                self.date.push(c)
+            }
+            _ => {}
+        }
+        None
+    }
+}
+
+impl Widget for CleanDialog {
+    fn render(self, area: Rect, buf: &mut ratatui::buffer::Buffer) {
+        let option = |index: usize, spans: Vec<Span<'static>>| {
+            let marker = if self.choice == index { "> " } else { "  " };
+            let mut line = vec![Span::raw(marker)];
+            line.extend(spans);
+            let line = Line::from(line);
+            if self.choice == index {
+                line.bold()
+            } else {
+                line
+            }
+        };
+        let field = |value: &str, index: usize| {
+            let cursor = if self.choice == index { "█" } else { "" };
+            Span::raw(format!("{value}{cursor}")).fg(Color::Yellow)
+        };
+        let mut lines = vec![
+            option(
+                0,
+                vec![
+                    Span::raw("Keep the newest "),
+                    field(&self.keep, 0),
+                    Span::raw(" versions of each package"),
+                ],
+            ),
+            option(
+                1,
+                vec![Span::raw("Remove every file of uninstalled packages")],
+            ),
+            option(
+                2,
+                vec![Span::raw("Remove files older than "), field(&self.date, 2)],
+            ),
+            Line::from(""),
+        ];
+        let files = self.files();
+        let size: u64 = files.iter().map(|c| c.size).sum();
+        lines.push(Line::from(format!(
+            "Would delete {} files, freeing {}",
+            files.len(),
+            human_size(size as usize)
+        )));
+        for file in files.iter().take(PREVIEW_FILES) {
+            lines.push(Line::from(format!("  {}", file.path.display())).fg(Color::Red));
+        }
+        if files.len() > PREVIEW_FILES {
+            lines.push(Line::from(format!(
+                "  and {} more",
+                files.len() - PREVIEW_FILES
+            )));
+        }
+        lines.push(Line::from(""));
+        if let Some(error) = &self.error {
+            lines.push(Line::from(error.clone()).fg(Color::Red));
+        }
+        lines.push(Line::from("Enter: Delete  Tab: Next policy  Esc: Cancel").fg(Color::Yellow));
+        render_dialog("Clean cache", lines, area, buf);
+    }
+}
@@ -0,0 +1,45 @@
+use ratatui::{
+    crossterm::event::{KeyCode, KeyEvent},
+    layout::Rect,
+    style::{Color, Stylize},
+    text::Line,
+    widgets::Widget,
+};
+
+use crate::{
+    structs::event::{EventCommand, EventResult},
+    widgets::render_dialog,
+};
+
+/// Asks a yes or no question before running a command
+#[derive(Debug, Clone)]
+pub struct ConfirmDialog {
+    command: EventCommand,
+    question: String,
+}
+
+impl ConfirmDialog {
+    pub fn new(command: EventCommand, question: String) -> Self {
+        Self { command, question }
+    }
+
+    /// Returns Some when the dialog should close, with the command to run if confirmed
+    pub fn handle_key_event(&self, key: &KeyEvent) -> Option<EventResult> {
+        match key.code {
+            KeyCode::Char('y') | KeyCode::Enter => Some(EventResult::Command(self.command.clone())),
+            KeyCode::Char('n') | KeyCode::Esc => Some(EventResult::None),
+            _ => None,
+        }
+    }
+}
+
+impl Widget for ConfirmDialog {
+    fn render(self, area: Rect, buf: &mut ratatui::buffer::Buffer) {
+        let lines = vec![
+            Line::from(self.question),
+            Line::from(""),
+            Line::from("y/Enter: Yes  n/Esc: No").fg(Color::Yellow),
+        ];
+        render_dialog("Confirm", lines, area, buf);
+    }
+}
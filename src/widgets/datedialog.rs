@@ -0,0 +1,132 @@
+use ratatui::{
+    crossterm::event::{KeyCode, KeyEvent},
+    layout::Rect,
+    style::{Color, Stylize},
+    text::{Line, Span},
+    widgets::Widget,
+};
+
+use crate::widgets::render_dialog;
+
+/// Dates as typed, `2024`, `2024-02` and `2024-02-15` are all allowed.
+/// Either end may be empty for an open range
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DateRange {
+    pub from: String,
+    pub to: String,
+}
+
+impl DateRange {
+    pub fn is_empty(&self) -> bool {
+        self.from.is_empty() && self.to.is_empty()
+    }
+
+    /// Whether a `YYYY-MM-DD ...` date falls in the range, both ends included
+    pub fn contains(&self, date: &str) -> bool {
+        let after_from = self.from.is_empty() || date >= self.from.as_str();
+        let before_to =
+            self.to.is_empty() || date.get(..self.to.len()).unwrap_or(date) <= self.to.as_str();
+        after_from && before_to
+    }
+}
+
+/// `YYYY`, `YYYY-MM`, `YYYY-MM-DD` or empty
+pub fn valid_date(date: &str) -> bool {
+    let parts: Vec<&str> = date.split('-').collect();
+    date.is_empty()
+        || (parts.len() <= 3
+            && parts[0].len() == 4
+            && parts[1..].iter().all(|p| p.len() == 2)
+            && parts.iter().all(|p| p.chars().all(|c| c.is_ascii_digit())))
+}
+
+/// Edit the date range the history is filtered by
+#[derive(Debug, Clone, Default)]
+pub struct DateDialog {
+    range: DateRange,
+    editing_to: bool,
+    error: Option<String>,
+}
+
+impl DateDialog {
+    pub fn new(range: DateRange) -> Self {
+        Self {
+            range,
+            ..Default::default()
+        }
+    }
+
+    /// Returns Some when the dialog should close, with the new range if confirmed
+    pub fn handle_key_event(&mut self, key: &KeyEvent) -> Option<Option<DateRange>> {
+        let field = if self.editing_to {
+            &mut self.range.to
+        } else {
+            &mut self.range.from
+        };
+        match key.code {
+            KeyCode::Esc => return Some(None),
+            KeyCode::Enter => {
+                if valid_date(&self.range.from) && valid_date(&self.range.to) {
+                    return Some(Some(self.range.clone()));
+                }
+                self.error = Some("Dates are YYYY, YYYY-MM or YYYY-MM-DD".to_string());
+            }
+            KeyCode::Tab | KeyCode::BackTab | KeyCode::Up | KeyCode::Down => {
+                self.editing_to = !self.editing_to
+            }
+            KeyCode::Backspace => {
+                field.pop();
+            }
+            KeyCode::Char(c) if c.is_ascii_digit() || c == '-' => field.push(c),
+            _ => {}
+        }
+        None
+    }
+}
+
+impl Widget for DateDialog {
+    fn render(self, area: Rect, buf: &mut ratatui::buffer::Buffer) {
+        let field = |name: &str, value: &str, editing: bool| {
+            let cursor = if editing { "█" } else { "" };
+            Line::from(vec![
+                Span::raw(format!("{name}: ")),
+                Span::raw(format!("{value}{cursor}")).fg(Color::Yellow),
+            ])
+        };
+        let mut lines = vec![
+            field("From", &self.range.from, !self.editing_to),
+            field("To  ", &self.range.to, self.editing_to),
+            Line::from(""),
+        ];
+        if let Some(error) = &self.error {
+            lines.push(Line::from(error.clone()).fg(Color::Red));
+        }
+        lines.push(Line::from("Leave empty for no limit"));
+        lines.push(Line::from(""));
+        lines.push(Line::from("Enter: Apply  Tab: Next field  Esc: Cancel").fg(Color::Yellow));
+        render_dialog("Date range", lines, area, buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_date_range() {
+        let range = DateRange {
+            from: "2024-02".to_string(),
+            to: "2024-02-15".to_string(),
+        };
+        assert!(range.contains("2024-02-01 10:00:00"));
+        assert!(range.contains("2024-02-15 23:59:59"));
+        assert!(!range.contains("2024-01-31 23:59:59"));
+        assert!(!range.contains("2024-02-16 00:00:00"));
+        assert!(DateRange::default().contains("1999-01-01 00:00"));
+
+        assert!(valid_date("2024"));
+        assert!(valid_date("2024-02-15"));
+        assert!(!valid_date("24-02"));
+        assert!(!valid_date("2024-2"));
+    }
+}
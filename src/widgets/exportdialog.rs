@@ -0,0 +1,123 @@
+use ratatui::{
+    crossterm::event::{KeyCode, KeyEvent},
+    layout::Rect,
+    style::{Color, Style, Stylize},
+    text::{Line, Span},
+    widgets::Widget,
+};
+
+use crate::{
+    graph::{GraphDirection, GraphFormat, GraphOptions, PackageGraph, export_graph},
+    structs::event::EventResult,
+    widgets::render_dialog,
+};
+
+/// Lets the user pick graph options and writes the subgraph of the roots to the working directory
+#[derive(Debug, Clone)]
+pub struct ExportDialog {
+    roots: Vec<String>,
+    options: GraphOptions,
+}
+
+impl ExportDialog {
+    pub fn new(roots: Vec<String>) -> Self {
+        Self {
+            roots,
+            options: GraphOptions::default(),
+        }
+    }
+
+    /// File name the graph is written to, named after the first root
+    pub fn file_name(&self) -> String {
+        format!(
+            "{}-deps.{}",
+            self.roots.first().map(|s| s.as_str()).unwrap_or("graph"),
+            self.options.format.extension()
+        )
+    }
+
+    fn export(&self, graph: &PackageGraph) -> EventResult {
+        let out = export_graph(&self.roots, graph, &self.options);
+        let name = self.file_name();
+        match std::fs::write(&name, out) {
+            Ok(()) => EventResult::Message(format!("Wrote {name}")),
+            Err(e) => EventResult::Message(format!("Failed to write {name}: {e}")),
+        }
+    }
+
+    /// Returns Some when the dialog should close
+    pub fn handle_key_event(
+        &mut self,
+        key: &KeyEvent,
+        graph: &PackageGraph,
+    ) -> Option<EventResult> {
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => return Some(EventResult::None),
+            KeyCode::Enter => return Some(self.export(graph)),
+            KeyCode::Char('d') => {
+                self.options.direction = match self.options.direction {
+                    GraphDirection::Forward => GraphDirection::Reverse,
+                    GraphDirection::Reverse => GraphDirection::Both,
+                    GraphDirection::Both => GraphDirection::Forward,
+                }
+            }
+            KeyCode::Char('f') => {
+                self.options.format = match self.options.format {
+                    GraphFormat::Dot => GraphFormat::Mermaid,
+                    GraphFormat::Mermaid => GraphFormat::Dot,
+                }
+            }
+            KeyCode::Char('o') => self.options.optional = !self.options.optional,
+            KeyCode::Char('+') => {
+                self.options.depth = Some(self.options.depth.map_or(1, |d| d + 1))
+            }
+            KeyCode::Char('-') => {
+                self.options.depth = match self.options.depth {
+                    None | Some(1) => None,
+                    Some(d) => Some(d - 1),
+                }
+            }
+            _ => {}
+        }
+        None
+    }
+}
+
+fn option_line(key: &str, name: &str, value: String) -> Line<'static> {
+    Line::from(vec![
+        Span::styled(format!(" {key} "), Style::new().black().on_yellow()),
+        Span::raw(format!(" {name}: {value}")),
+    ])
+}
+
+impl Widget for ExportDialog {
+    fn render(self, area: Rect, buf: &mut ratatui::buffer::Buffer) {
+        let direction = match self.options.direction {
+            GraphDirection::Forward => "dependencies",
+            GraphDirection::Reverse => "required by",
+            GraphDirection::Both => "both",
+        };
+        let format = match self.options.format {
+            GraphFormat::Dot => "DOT",
+            GraphFormat::Mermaid => "Mermaid",
+        };
+        let depth = self
+            .options
+            .depth
+            .map_or("unlimited".to_string(), |d| d.to_string());
+
+        let lines = vec![
+            Line::from(format!("Packages: {}", self.roots.join(" "))),
+            Line::from(""),
+            option_line("d", "Direction", direction.to_string()),
+            option_line("+/-", "Depth", depth),
+            option_line("o", "Optional", self.options.optional.to_string()),
+            option_line("f", "Format", format.to_string()),
+            Line::from(""),
+            Line::from(format!("Writes {}", self.file_name())),
+            Line::from(""),
+            Line::from("Enter: Export  Esc: Cancel").fg(Color::Yellow),
+        ];
+        render_dialog("Export dependency graph", lines, area, buf);
+    }
+}
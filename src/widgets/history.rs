@@ -0,0 +1,226 @@
+use ratatui::{
+    crossterm::event::{KeyCode, KeyEvent},
+    layout::{Constraint, Rect},
+    style::Color,
+    widgets::Widget,
+};
+
+use std::sync::Arc;
+
+use crate::{
+    db::log::{LogAction, Transaction},
+    error::AppError,
+    graph::PackageGraph,
+    structs::{
+        event::{BackgroundJob, EventResult},
+        package::Package,
+    },
+    widgets::{
+        Commands, CurrentPackage,
+        datedialog::{DateDialog, DateRange},
+        table::{Sort, TableRow, TableWidget},
+    },
+};
+
+/// Every package change from `pacman.log`, newest first.
+/// Read when the tab is opened and after every reload
+#[derive(Debug, Clone)]
+pub struct HistoryWidget {
+    graph: Arc<PackageGraph>,
+    transactions: Vec<Transaction>,
+    loaded: bool,
+    error: Option<String>,
+    ///only show this package
+    package: Option<String>,
+    range: DateRange,
+    table: TableWidget,
+    date_dialog: Option<DateDialog>,
+}
+
+impl Default for HistoryWidget {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HistoryWidget {
+    pub fn new() -> Self {
+        Self {
+            graph: Arc::default(),
+            transactions: vec![],
+            loaded: false,
+            error: None,
+            package: None,
+            range: DateRange::default(),
+            table: TableWidget::new(
+                &["Date", "Action", "Package", "Old", "New", "Command"],
+                vec![
+                    Constraint::Length(19),
+                    Constraint::Length(11),
+                    Constraint::Percentage(25),
+                    Constraint::Percentage(15),
+                    Constraint::Percentage(15),
+                    Constraint::Percentage(45),
+                ],
+            )
+            .with_sort(0, Sort::Desc),
+            date_dialog: None,
+        }
+    }
+
+    /// Returns the job reading the log again once the tab was opened
+    pub fn set_data(&mut self, graph: Arc<PackageGraph>) -> Option<BackgroundJob> {
+        self.graph = graph;
+        self.loaded.then(|| self.refresh())
+    }
+
+    /// Read the log again
+    pub fn refresh(&mut self) -> BackgroundJob {
+        self.loaded = true;
+        self.table.set_title("Reading pacman.log…");
+        BackgroundJob::History
+    }
+
+    /// Show the transactions read in the background, before the tab was opened they are not needed
+    pub fn set_history(&mut self, res: Result<Vec<Transaction>, AppError>) {
+        if !self.loaded {
+            return;
+        }
+        match res {
+            Ok(transactions) => {
+                self.transactions = transactions;
+                self.error = None;
+            }
+            Err(e) => {
+                self.transactions.clear();
+                self.error = Some(format!("Error reading pacman.log: {e}"));
+            }
+        }
+        self.filter_data();
+    }
+
+    fn filter_data(&mut self) {
+        let rows: Vec<TableRow> = self
+            .transactions
+            .iter()
+            .rev()
+            .flat_map(|t| t.entries.iter().rev().map(move |e| (t, e)))
+            .filter(|(_, e)| self.package.as_ref().is_none_or(|p| *p == e.package))
+            .filter(|(_, e)| self.range.contains(&e.date))
+            .map(|(t, e)| {
+                TableRow::new(vec![
+                    e.date.clone(),
+                    e.action.to_string(),
+                    e.package.clone(),
+                    e.old_version.clone().unwrap_or_default(),
+                    e.new_version.clone().unwrap_or_default(),
+                    t.command.clone().unwrap_or_default(),
+                ])
+                .with_highlight(match e.action {
+                    LogAction::Installed => Some(Color::Green),
+                    LogAction::Removed => Some(Color::Red),
+                    LogAction::Downgraded => Some(Color::Magenta),
+                    LogAction::Reinstalled => Some(Color::Blue),
+                    LogAction::Upgraded => None,
+                })
+            })
+            .collect();
+        self.table.set_data(rows);
+        self.update_title();
+    }
+
+    fn update_title(&mut self) {
+        if let Some(error) = &self.error {
+            self.table.set_title(&error.clone());
+            return;
+        }
+        let mut filters = vec![];
+        if let Some(package) = &self.package {
+            filters.push(package.clone());
+        }
+        if !self.range.is_empty() {
+            filters.push(format!("{}..{}", self.range.from, self.range.to));
+        }
+        let filters = if filters.is_empty() {
+            String::new()
+        } else {
+            format!(" Filters: {}", filters.join(" "))
+        };
+        self.table.set_title(&format!(
+            "{} changes in {} transactions{filters}",
+            self.table.rows().len(),
+            self.transactions.len()
+        ));
+    }
+}
+
+impl Widget for HistoryWidget {
+    fn render(self, area: Rect, buf: &mut ratatui::buffer::Buffer) {
+        self.table.render(area, buf);
+        if let Some(dialog) = self.date_dialog {
+            dialog.render(area, buf);
+        }
+    }
+}
+
+impl Commands for HistoryWidget {
+    fn command_descriptions(&self) -> Vec<(&str, &str, &str)> {
+        vec![
+            ("p", "Only show the current package", "Package"),
+            ("d", "Filter by date range", "Dates"),
+            ("Esc", "Clear filters", ""),
+            ("Enter", "Go to package in Installed", "Installed"),
+        ]
+    }
+
+    fn handle_key_event(&mut self, key: &KeyEvent) -> Option<EventResult> {
+        //dialog takes all keys while open
+        if let Some(dialog) = self.date_dialog.as_mut() {
+            if let Some(res) = dialog.handle_key_event(key) {
+                self.date_dialog = None;
+                if let Some(range) = res {
+                    self.range = range;
+                    self.filter_data();
+                }
+            }
+            return Some(EventResult::None);
+        }
+        if self.table.handle_key_event(key) {
+            self.update_title(); //may have filtered
+            return Some(EventResult::None);
+        }
+        let current = self.table.get_current().map(|r| r.cells[2].clone());
+        match key.code {
+            KeyCode::Char('p') => {
+                self.package = match self.package {
+                    Some(_) => None,
+                    None => current,
+                };
+                self.filter_data();
+            }
+            KeyCode::Char('d') => self.date_dialog = Some(DateDialog::new(self.range.clone())),
+            KeyCode::Esc => {
+                self.package = None;
+                self.range = DateRange::default();
+                self.filter_data();
+            }
+            KeyCode::Enter => {
+                let name = current?;
+                if self.graph.get(&name).is_some_and(|p| p.installed.is_some()) {
+                    return Some(EventResult::GotoInstalled(name));
+                }
+                return Some(EventResult::Message(format!("{name} is not installed")));
+            }
+            _ => return None,
+        }
+        Some(EventResult::None)
+    }
+}
+
+impl CurrentPackage for HistoryWidget {
+    fn current_package(&self) -> Option<&Package> {
+        self.table
+            .get_current()
+            .and_then(|r| self.graph.get(&r.cells[2]))
+    }
+}
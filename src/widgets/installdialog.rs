@@ -0,0 +1,97 @@
+use ratatui::{
+    crossterm::event::{KeyCode, KeyEvent},
+    layout::Rect,
+    style::{Color, Stylize},
+    text::Line,
+    widgets::Widget,
+};
+
+use crate::{
+    plan::InstallPlan,
+    structs::event::{EventCommand, EventResult},
+    utils::human_size,
+    widgets::render_dialog,
+};
+
+/// Shows the dependencies an install would pull in before running it
+#[derive(Debug, Clone)]
+pub struct InstallDialog {
+    plan: InstallPlan,
+}
+
+impl InstallDialog {
+    pub fn new(plan: InstallPlan) -> Self {
+        Self { plan }
+    }
+
+    /// Returns Some when the dialog should close, with the command to run if confirmed
+    pub fn handle_key_event(&mut self, key: &KeyEvent) -> Option<EventResult> {
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => Some(EventResult::None),
+            KeyCode::Enter => Some(EventResult::Command(EventCommand::InstallOrUpdateSelected(
+                self.plan.targets.clone(),
+            ))),
+            _ => None,
+        }
+    }
+}
+
+impl Widget for InstallDialog {
+    fn render(self, area: Rect, buf: &mut ratatui::buffer::Buffer) {
+        let plan = &self.plan;
+        let mut lines = vec![Line::from(format!("Install: {}", plan.targets.join(" ")))];
+
+        if plan.dependencies.is_empty() {
+            lines.push(Line::from("No new dependencies"));
+        } else {
+            lines.push(Line::from(""));
+            lines.push(Line::from(format!(
+                "New dependencies {}:",
+                plan.dependencies.len()
+            )));
+            lines.extend(
+                plan.dependencies
+                    .iter()
+                    .map(|(dep, by)| Line::from(format!("  {dep} (for {by})"))),
+            );
+        }
+        if !plan.upgrades.is_empty() {
+            lines.push(Line::from(""));
+            lines.push(Line::from(format!(
+                "Upgraded dependencies {}:",
+                plan.upgrades.len()
+            )));
+            lines.extend(
+                plan.upgrades
+                    .iter()
+                    .map(|(dep, by)| Line::from(format!("  {dep} (for {by})"))),
+            );
+        }
+        if !plan.missing.is_empty() {
+            lines.push(Line::from(""));
+            lines.push(Line::from(format!("Not found {}:", plan.missing.len())).fg(Color::Red));
+            lines.extend(
+                plan.missing
+                    .iter()
+                    .map(|(dep, by)| Line::from(format!("  {dep} (for {by})")).fg(Color::Red)),
+            );
+        }
+        if !plan.conflicts.is_empty() {
+            lines.push(Line::from(""));
+            lines.push(Line::from(format!("Conflicts {}:", plan.conflicts.len())).fg(Color::Red));
+            lines.extend(plan.conflicts.iter().map(|(new, old)| {
+                Line::from(format!("  {new} conflicts with installed {old}")).fg(Color::Red)
+            }));
+        }
+        lines.push(Line::from(""));
+        lines.push(Line::from(format!(
+            "Download {}, installed size {}",
+            human_size(plan.download_size),
+            human_size(plan.installed_size)
+        )));
+        lines.push(Line::from(""));
+        lines.push(Line::from("Enter: Run  Esc: Cancel").fg(Color::Yellow));
+
+        render_dialog("Install with pacman -S", lines, area, buf);
+    }
+}
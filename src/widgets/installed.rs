@@ -1,27 +1,42 @@
 use ratatui::{
     crossterm::event::{KeyCode, KeyEvent},
     layout::{Constraint, Layout, Rect},
-    macros::text,
     style::Color,
     widgets::Widget,
 };
 
+use std::{path::PathBuf, sync::Arc};
+
 use crate::{
-    pman::get_provides,
-    structs::{event::EventResult, package::Package, reason::Reason},
+    error::AppError,
+    graph::{MAX_WHY_PATHS, PackageGraph, dependency_tree, why_paths},
+    integrity::{FileIssue, FileProblem},
+    owner::OwnerScan,
+    plan::RemovalPlan,
+    source::{PackageSource, pacman::PacmanSource},
+    structs::{
+        event::{BackgroundJob, EventResult},
+        package::Package,
+        reason::Reason,
+    },
     widgets::{
         Commands, CurrentPackage,
+        exportdialog::ExportDialog,
+        ownerdialog::OwnerDialog,
+        removedialog::RemoveDialog,
         table::{TableFocus, TableRow, TableWidget},
+        tree::TreeWidget,
     },
 };
 
 #[derive(Clone, Debug)]
 pub struct InstalledWidget {
-    data: Vec<Package>,
+    source: Arc<dyn PackageSource>,
+    graph: Arc<PackageGraph>,
     filter_orphans: bool,
     filter_foreign: bool,
     filter_explicit: bool,
-    show_providing: bool,
+    lower_pane: LowerPane,
 
     pub prev: Vec<String>,
 
@@ -31,20 +46,39 @@ pub struct InstalledWidget {
     left: TableWidget,
     right: TableWidget,
     centre: TableWidget,
-    provides: TableWidget,
+    lower: TableWidget,
+    tree: TreeWidget,
+    ///package the trees were built for, moving around inside them keeps it
+    tree_for: Option<String>,
+    integrity: TableWidget,
+
+    remove_dialog: Option<RemoveDialog>,
+    export_dialog: Option<ExportDialog>,
+    owner_dialog: Option<OwnerDialog>,
 }
 
 impl Default for InstalledWidget {
     fn default() -> Self {
+        Self::new(Arc::new(PacmanSource::default()))
+    }
+}
+
+impl InstalledWidget {
+    pub fn new(source: Arc<dyn PackageSource>) -> Self {
         Self {
-            data: vec![],
+            source,
+            graph: Arc::default(),
             filter_explicit: false,
             filter_foreign: false,
             filter_orphans: false,
             prev: vec![],
             focus: FocusedTable::Centre,
             previous_focus: FocusedTable::Centre,
-            left: TableWidget::new(&["Name"], vec![Constraint::Percentage(100)]).with_no_focus(),
+            left: TableWidget::new(
+                &["Name", "Version"],
+                vec![Constraint::Percentage(60), Constraint::Percentage(40)],
+            )
+            .with_no_focus(),
             centre: TableWidget::new(
                 &[
                     "Name",
@@ -66,15 +100,30 @@ impl Default for InstalledWidget {
                 ],
             ),
             right: TableWidget::new(&["Name"], vec![Constraint::Percentage(100)]).with_no_focus(),
-            provides: TableWidget::new(&[], vec![Constraint::Percentage(100)]).with_no_focus(),
-            show_providing: false,
+            lower: TableWidget::new(&[], vec![Constraint::Percentage(100)]).with_no_focus(),
+            tree: TreeWidget::new(),
+            tree_for: None,
+            integrity: TableWidget::new(
+                &["Package", "File", "Problem"],
+                vec![
+                    Constraint::Percentage(20),
+                    Constraint::Percentage(50),
+                    Constraint::Percentage(30),
+                ],
+            )
+            .with_no_focus(),
+            lower_pane: LowerPane::None,
+            remove_dialog: None,
+            export_dialog: None,
+            owner_dialog: None,
         }
     }
 }
 
 impl InstalledWidget {
-    pub fn set_data(&mut self, data: Vec<Package>) {
-        self.data = data;
+    pub fn set_data(&mut self, graph: Arc<PackageGraph>) {
+        self.graph = graph;
+        self.tree_for = None;
         self.filter_data();
     }
     fn reset_filters(&mut self) {
@@ -86,9 +135,8 @@ impl InstalledWidget {
     fn filter_data(&mut self) {
         //installed
         let packs: Vec<_> = self
-            .data
-            .iter()
-            .filter(|p| p.installed.is_some())
+            .graph
+            .installed()
             .filter(|p| !self.filter_explicit || p.reason == Reason::Explicit) //only show explicit packages
             .filter(|p| !self.filter_foreign || !p.validated) //only show foreign packages
             .filter(|p| {
@@ -188,13 +236,23 @@ impl InstalledWidget {
         });
     }
     fn cycle_focus_vert(&mut self) {
-        if self.show_providing {
+        if self.lower_pane != LowerPane::None {
             self.change_focus(match self.focus {
-                FocusedTable::Providing => self.previous_focus,
-                _ => FocusedTable::Providing,
+                FocusedTable::Lower => self.previous_focus,
+                _ => FocusedTable::Lower,
             });
         }
     }
+    fn toggle_lower_pane(&mut self, pane: LowerPane) {
+        self.lower_pane = if self.lower_pane == pane {
+            LowerPane::None
+        } else {
+            pane
+        };
+        if self.lower_pane == LowerPane::None && self.focus == FocusedTable::Lower {
+            self.change_focus(self.previous_focus);
+        }
+    }
 
     fn update_dependency_tables(&mut self) {
         //dependents
@@ -203,8 +261,8 @@ impl InstalledWidget {
             self.left.set_title("Depends on 0");
             self.right.set_data(vec![]);
             self.right.set_title("Required by 0");
-            self.provides.set_data(vec![]);
-            self.provides.set_title("0 files");
+            self.lower.set_data(vec![]);
+            self.lower.set_title("");
             return;
         };
         let mut pack = pack.clone();
@@ -236,19 +294,37 @@ impl InstalledWidget {
             .dependencies
             .iter()
             .map(|dep| {
-                let col: Option<Color> = match self.get_pack(dep) {
+                let mut constraint = dep.constraint();
+                let provider = self.get_pack(&dep.name);
+                if let Some(p) = provider
+                    && p.name != dep.name
+                {
+                    constraint = format!("{constraint} via {}", p.name);
+                }
+                let col: Option<Color> = match provider {
+                    Some(p) if !p.satisfies(dep) => {
+                        constraint = format!("{constraint} ({})", p.version);
+                        Some(Color::Magenta)
+                    }
                     Some(p) if p.reason == Reason::Explicit => Some(Color::Green),
                     Some(_) => None,
                     None => Some(Color::Red),
                 };
 
-                TableRow::new(vec![dep.clone()]).with_highlight(col)
+                TableRow::new(vec![dep.name.clone(), constraint]).with_highlight(col)
             })
             .collect();
+        let unsatisfied = pack
+            .dependencies
+            .iter()
+            .filter(|dep| self.get_pack(&dep.name).is_some_and(|p| !p.satisfies(dep)))
+            .count();
         let rows_optional: Vec<TableRow> = pack
             .dependencies_optional
             .iter()
-            .map(|dep| TableRow::new(vec![dep.clone()]).with_highlight(Some(Color::Blue)))
+            .map(|dep| {
+                TableRow::new(vec![dep.clone(), String::new()]).with_highlight(Some(Color::Blue))
+            })
             .collect();
 
         rows.extend(rows_optional);
@@ -257,35 +333,129 @@ impl InstalledWidget {
         let count_optional = pack.dependencies_optional.len();
         let optional = format!("(+{count_optional} optional)");
         let title = format!(
-            "Depends on {count} {}",
+            "Depends on {count} {}{}",
             if count_optional > 0 {
                 optional
             } else {
                 "".to_string()
+            },
+            if unsatisfied > 0 {
+                format!(" {unsatisfied} unsatisfied")
+            } else {
+                "".to_string()
             }
         );
         self.left.set_title(&title);
         self.left.set_data(rows);
 
-        //provides
-        if self.show_providing
-            && pack.provides.is_none()
-            && let Ok(prov) = get_provides(&pack.name)
-        {
-            pack.provides = Some(prov);
+        match self.lower_pane {
+            LowerPane::None | LowerPane::Integrity => {}
+            LowerPane::Files => {
+                if pack.files.is_none()
+                    && let Ok(files) = self.source.files(&pack)
+                {
+                    pack.files = Some(files);
+                }
+                let rows: Vec<TableRow> = pack
+                    .files
+                    .unwrap_or_default()
+                    .iter()
+                    .filter(|p| !p.ends_with('/'))
+                    .map(|p| TableRow::new(vec![p.clone()]))
+                    .collect();
+                self.lower.set_title(&format!("{} files", rows.len()));
+                self.lower.set_data(rows);
+            }
+            LowerPane::Why => {
+                let paths = why_paths(&pack.name, &self.graph);
+                let title = if pack.reason == Reason::Explicit {
+                    format!("Why: {} is explicitly installed", pack.name)
+                } else if paths.len() >= MAX_WHY_PATHS {
+                    format!("Why: first {} paths from explicit packages", paths.len())
+                } else {
+                    format!("Why: {} paths from explicit packages", paths.len())
+                };
+                let rows = paths
+                    .iter()
+                    .map(|path| TableRow::new(vec![path.join(" → ")]))
+                    .collect();
+                self.lower.set_title(&title);
+                self.lower.set_data(rows);
+            }
+            LowerPane::Tree if self.tree_for.as_deref() == Some(pack.name.as_str()) => {}
+            LowerPane::Tree => {
+                let mut forward = dependency_tree(&pack.name, &self.graph, false);
+                forward.label = format!("{} depends on", pack.name);
+                let mut reverse = dependency_tree(&pack.name, &self.graph, true);
+                reverse.label = format!("{} is required by", pack.name);
+                self.tree
+                    .set_title("Tree (+/-/Space expand, * expand all, Enter jump)");
+                self.tree.set_roots(vec![forward, reverse]);
+                self.tree_for = Some(pack.name.clone());
+            }
+        }
+    }
+    /// Check the files of the selected packages in the background, like `pacman -Qkk`
+    fn check_selected(&mut self) -> EventResult {
+        let packs: Vec<Package> = self
+            .selected_or_current()
+            .iter()
+            .filter_map(|name| self.graph.get(name).cloned())
+            .collect();
+        self.integrity
+            .set_title(&format!("Checking {} packages…", packs.len()));
+        self.integrity.set_data(vec![]);
+        self.lower_pane = LowerPane::Integrity;
+        EventResult::Background(BackgroundJob::CheckFiles(packs))
+    }
+    /// Hand the owners found in the background to the owner dialog, if it is still open
+    pub fn set_owners(&mut self, paths: &[PathBuf], scan: OwnerScan) {
+        if let Some(dialog) = self.owner_dialog.as_mut() {
+            dialog.set_owners(paths, scan);
         }
-        if let Some(prov) = pack.provides {
-            let rows: Vec<TableRow> = prov
-                .iter()
-                .filter(|p| !p.ends_with('/'))
-                .map(|p| TableRow::new(vec![p.clone()]))
-                .collect();
-            self.provides.set_title(&format!("{} files", rows.len()));
-            self.provides.set_data(rows);
+    }
+    /// Show the check results, they stay in the lower pane while moving around
+    pub fn set_file_issues(&mut self, results: Vec<(String, Result<Vec<FileIssue>, AppError>)>) {
+        let checked = results.len();
+        let mut rows = vec![];
+        let mut failed = 0;
+        for (name, issues) in results {
+            let issues = match issues {
+                Ok(issues) => issues,
+                Err(e) => {
+                    rows.push(
+                        TableRow::new(vec![name, String::new(), e.to_string()])
+                            .with_highlight(Some(Color::DarkGray)),
+                    );
+                    continue;
+                }
+            };
+            if !issues.is_empty() {
+                failed += 1;
+            }
+            for issue in issues {
+                let color = if issue.problem == FileProblem::Missing {
+                    Color::Red
+                } else if issue.problem.is_serious() {
+                    Color::Magenta
+                } else {
+                    Color::Yellow
+                };
+                rows.push(
+                    TableRow::new(vec![name.clone(), issue.path, issue.problem.to_string()])
+                        .with_highlight(Some(color)),
+                );
+            }
         }
+        self.integrity.set_title(&format!(
+            "Check: {} issues, {failed} of {checked} packages changed",
+            rows.len(),
+        ));
+        self.integrity.set_data(rows);
     }
+    ///find by name, else the package providing that name
     fn get_pack(&self, name: &str) -> Option<&Package> {
-        self.data.iter().find(|p| p.name == name)
+        self.graph.resolve(name)
     }
     fn goto_package(&mut self, name: &str) {
         self.change_focus(FocusedTable::Centre);
@@ -316,30 +486,40 @@ impl InstalledWidget {
         self.left.focus(TableFocus::Unfocused);
         self.centre.focus(TableFocus::UnfocusedDimmed);
         self.right.focus(TableFocus::Unfocused);
-        self.provides.focus(TableFocus::Unfocused);
+        self.lower.focus(TableFocus::Unfocused);
+        self.tree.focus(TableFocus::Unfocused);
+        self.integrity.focus(TableFocus::Unfocused);
         match self.focus {
             FocusedTable::Left => self.left.focus(TableFocus::Focused),
             FocusedTable::Centre => self.centre.focus(TableFocus::Focused),
             FocusedTable::Right => self.right.focus(TableFocus::Focused),
-            FocusedTable::Providing => self.provides.focus(TableFocus::Focused),
+            FocusedTable::Lower => {
+                self.lower.focus(TableFocus::Focused);
+                self.tree.focus(TableFocus::Focused);
+                self.integrity.focus(TableFocus::Focused);
+            }
         }
     }
     fn handle_enter(&mut self) {
         let new = match self.focus {
-            FocusedTable::Left => self.left.get_current(),
-            FocusedTable::Right => self.right.get_current(),
+            FocusedTable::Left => self.left.get_current().map(|r| &r.cells[0]),
+            FocusedTable::Right => self.right.get_current().map(|r| &r.cells[0]),
+            FocusedTable::Lower if self.lower_pane == LowerPane::Tree => {
+                self.tree.get_current().map(|n| &n.name)
+            }
+            FocusedTable::Lower if self.lower_pane == LowerPane::Integrity => {
+                self.integrity.get_current().map(|r| &r.cells[0])
+            }
             _ => return,
         };
         let Some(new) = new else {
             return;
         };
 
-        let new_name = new.cells[0].clone();
-
-        //check if it exists, else do nothing
-        if self.get_pack(&new_name).is_none() {
+        //check if it exists, else do nothing. Virtual names jump to the provider
+        let Some(new_name) = self.get_pack(new).map(|p| p.name.clone()) else {
             return;
-        }
+        };
 
         self.prev.push(
             self.current_package()
@@ -349,6 +529,23 @@ impl InstalledWidget {
         self.goto_package(&new_name);
     }
 
+    /// Names of the selected packages, or the current one if nothing is selected
+    fn selected_or_current(&self) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .centre
+            .get_selected()
+            .into_iter()
+            .filter_map(|c| c.cells.first())
+            .cloned()
+            .collect();
+        if names.is_empty()
+            && let Some(pack) = self.current_package()
+        {
+            names.push(pack.name.clone());
+        }
+        names
+    }
+
     pub(crate) fn goto_package_by_name(&mut self, name: &str) {
         self.goto_package(name);
     }
@@ -357,12 +554,29 @@ impl InstalledWidget {
 impl Commands for InstalledWidget {
     fn command_descriptions(&self) -> Vec<(&str, &str, &str)> {
         vec![
-            ("r", "Remove selected packages", "Remove"),
+            ("r", "Remove selected packages (with preview)", "Remove"),
+            (
+                "x",
+                "Export dependency graph of selected packages",
+                "Export",
+            ),
             ("e", "View explicitly installed packages", "Explicit"),
             ("f", "View foreign packages", "Foreign"),
             ("o", "View orphaned packages", "Orphans"),
             ("p", "View files provided by package", "Provides"),
-            ("P", "Focus providing packages table", ""),
+            ("w", "Why is package installed", "Why"),
+            (
+                "O",
+                "Find the installed package owning a path or command",
+                "Owner",
+            ),
+            ("t", "Recursive dependency and required by tree", "Tree"),
+            (
+                "c",
+                "Check files of selected packages for changes (pacman -Qkk)",
+                "Check",
+            ),
+            ("P", "Focus files/why/tree/check pane", ""),
             ("←/h", "Left dependency table", ""),
             ("→/l", "Right dependent table", ""),
             ("Backspace", "Go to previous package", ""),
@@ -370,11 +584,43 @@ impl Commands for InstalledWidget {
     }
 
     fn handle_key_event(&mut self, key: &KeyEvent) -> Option<EventResult> {
+        //dialog takes all keys while open
+        if let Some(dialog) = self.remove_dialog.as_mut() {
+            let res = dialog.handle_key_event(key);
+            if res.is_some() {
+                self.remove_dialog = None;
+            }
+            return Some(res.unwrap_or(EventResult::None));
+        }
+        if let Some(dialog) = self.owner_dialog.as_mut() {
+            let res = dialog.handle_key_event(key);
+            if matches!(res, Some(EventResult::Background(_))) {
+                return res;
+            }
+            if res.is_some() {
+                self.owner_dialog = None;
+            }
+            return Some(res.unwrap_or(EventResult::None));
+        }
+        if let Some(dialog) = self.export_dialog.as_mut() {
+            let res = dialog.handle_key_event(key, &self.graph);
+            if res.is_some() {
+                self.export_dialog = None;
+            }
+            return Some(res.unwrap_or(EventResult::None));
+        }
+
         let handled = match self.focus {
             FocusedTable::Left => self.left.handle_key_event(key),
             FocusedTable::Centre => self.centre.handle_key_event(key),
             FocusedTable::Right => self.right.handle_key_event(key),
-            FocusedTable::Providing => self.provides.handle_key_event(key),
+            FocusedTable::Lower if self.lower_pane == LowerPane::Tree => {
+                self.tree.handle_key_event(key)
+            }
+            FocusedTable::Lower if self.lower_pane == LowerPane::Integrity => {
+                self.integrity.handle_key_event(key)
+            }
+            FocusedTable::Lower => self.lower.handle_key_event(key),
         };
         if handled {
             self.filter_data(); //the filter may have changed what is currently selected, so we want to update that
@@ -383,17 +629,20 @@ impl Commands for InstalledWidget {
 
         match key.code {
             KeyCode::Char('r') => {
-                let selected_names = self
-                    .centre
-                    .get_selected()
-                    .into_iter()
-                    .filter_map(|c| c.cells.first())
-                    .cloned()
-                    .collect();
-
-                return Some(EventResult::Command(
-                    crate::structs::event::EventCommand::RemoveSelected(selected_names),
-                ));
+                let plan = RemovalPlan::new(&self.selected_or_current(), &self.graph);
+                self.remove_dialog = Some(RemoveDialog::new(plan));
+                return Some(EventResult::None);
+            }
+            KeyCode::Char('O') => {
+                self.owner_dialog = Some(OwnerDialog::new());
+                return Some(EventResult::None);
+            }
+            KeyCode::Char('x') => {
+                let roots = self.selected_or_current();
+                if !roots.is_empty() {
+                    self.export_dialog = Some(ExportDialog::new(roots));
+                }
+                return Some(EventResult::None);
             }
             KeyCode::Char('e') => self.filter_explicit = !self.filter_explicit,
             KeyCode::Char('f') => self.filter_foreign = !self.filter_foreign,
@@ -401,7 +650,10 @@ impl Commands for InstalledWidget {
             KeyCode::Left | KeyCode::Char('h') => self.cycle_focus_horiz(-1),
             KeyCode::Right | KeyCode::Char('l') => self.cycle_focus_horiz(1),
             KeyCode::Char('P') => self.cycle_focus_vert(),
-            KeyCode::Char('p') => self.show_providing = !self.show_providing,
+            KeyCode::Char('p') => self.toggle_lower_pane(LowerPane::Files),
+            KeyCode::Char('w') => self.toggle_lower_pane(LowerPane::Why),
+            KeyCode::Char('t') => self.toggle_lower_pane(LowerPane::Tree),
+            KeyCode::Char('c') => return Some(self.check_selected()),
             KeyCode::Backspace => {
                 if let Some(prev) = self.prev.pop() {
                     self.goto_package(&prev);
@@ -417,7 +669,7 @@ impl Commands for InstalledWidget {
 
 impl Widget for InstalledWidget {
     fn render(self, area: Rect, buf: &mut ratatui::buffer::Buffer) {
-        let pr = if self.show_providing {
+        let pr = if self.lower_pane != LowerPane::None {
             ((area.height as f32 * 0.5) as u16).max(5)
         } else {
             0
@@ -434,8 +686,21 @@ impl Widget for InstalledWidget {
         self.centre.render(areas[1], buf);
         self.right.render(areas[2], buf);
 
-        if self.show_providing {
-            self.provides.render(verti[1], buf);
+        match self.lower_pane {
+            LowerPane::None => {}
+            LowerPane::Tree => self.tree.render(verti[1], buf),
+            LowerPane::Integrity => self.integrity.render(verti[1], buf),
+            _ => self.lower.render(verti[1], buf),
+        }
+
+        if let Some(dialog) = self.remove_dialog {
+            dialog.render(area, buf);
+        }
+        if let Some(dialog) = self.export_dialog {
+            dialog.render(area, buf);
+        }
+        if let Some(dialog) = self.owner_dialog {
+            dialog.render(area, buf);
         }
     }
 }
@@ -445,12 +710,54 @@ enum FocusedTable {
     Left,
     Centre,
     Right,
-    Providing,
+    Lower,
+}
+
+///what is shown below the dependency tables
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum LowerPane {
+    None,
+    Files,
+    Why,
+    Tree,
+    Integrity,
 }
 
 impl CurrentPackage for InstalledWidget {
     fn current_package(&self) -> Option<&Package> {
         let curr = self.centre.get_current()?;
-        self.data.iter().find(|p| p.name == curr.cells[0])
+        self.graph.resolve(&curr.cells[0])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{pman::load_packages, source::fixture::FixtureSource};
+    use std::path::Path;
+
+    #[test]
+    fn test_check_selected() {
+        let source: Arc<dyn PackageSource> = Arc::new(FixtureSource::new(
+            &Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures"),
+        ));
+        let graph = load_packages(source.as_ref(), &|_| {}, &|_| {}).unwrap();
+        let mut widget = InstalledWidget::new(source.clone());
+        widget.set_data(Arc::new(graph));
+
+        //checking files runs in the background
+        widget.goto_package_by_name("bash");
+        let Some(EventResult::Background(BackgroundJob::CheckFiles(packs))) =
+            widget.handle_key_event(&KeyEvent::from(KeyCode::Char('c')))
+        else {
+            panic!("expected a background check");
+        };
+        assert_eq!(packs.len(), 1);
+        assert_eq!(packs[0].name, "bash");
+
+        let issues = source.check_files(&packs[0]);
+        widget.set_file_issues(vec![("bash".to_string(), issues)]);
+        assert!(widget.lower_pane == LowerPane::Integrity);
+        assert!(!widget.integrity.rows().is_empty());
     }
 }
@@ -1,10 +1,27 @@
-use ratatui::crossterm::event::KeyEvent;
+use ratatui::{
+    buffer::Buffer,
+    crossterm::event::KeyEvent,
+    layout::Rect,
+    text::Line,
+    widgets::{Block, Clear, Paragraph, Widget, Wrap},
+};
 
 use crate::structs::{event::EventResult, package::Package};
 
+pub mod cache;
+pub mod cleandialog;
+pub mod confirmdialog;
+pub mod datedialog;
+pub mod exportdialog;
+pub mod history;
+pub mod installdialog;
 pub mod installed;
+pub mod ownerdialog;
 pub mod packages;
+pub mod pacnew;
+pub mod removedialog;
 pub mod table;
+pub mod tree;
 pub mod update;
 
 pub trait Commands {
@@ -16,3 +33,26 @@ pub trait Commands {
 pub trait CurrentPackage {
     fn current_package(&self) -> Option<&Package>;
 }
+
+/// A rect in the centre of `area`, used for popups
+pub fn centered_rect(area: Rect, width: u16, height: u16) -> Rect {
+    let width = width.min(area.width);
+    let height = height.min(area.height);
+    Rect::new(
+        area.x + (area.width - width) / 2,
+        area.y + (area.height - height) / 2,
+        width,
+        height,
+    )
+}
+
+/// Draw a bordered popup with the given lines, at least half the height of `area`
+pub fn render_dialog(title: &str, lines: Vec<Line>, area: Rect, buf: &mut Buffer) {
+    let height = (lines.len() as u16 + 2).max(area.height / 2);
+    let rect = centered_rect(area, (area.width * 2 / 3).max(60), height);
+    Clear.render(rect, buf);
+    Paragraph::new(lines)
+        .wrap(Wrap { trim: false })
+        .block(Block::bordered().title(title))
+        .render(rect, buf);
+}
@@ -0,0 +1,219 @@
+use ratatui::{
+    crossterm::event::{KeyCode, KeyEvent},
+    layout::Rect,
+    style::{Color, Style, Stylize},
+    text::{Line, Span},
+    widgets::Widget,
+};
+
+use std::{path::PathBuf, sync::Arc};
+
+use crate::{
+    db::files::FilesDb,
+    owner::{Owner, OwnerScan, query_paths},
+    structs::event::{BackgroundJob, EventResult},
+    widgets::render_dialog,
+};
+
+/// Type a path or command name and find the installed package owning it, like `pacman -Qo`.
+/// With a files database it searches the repositories instead, like `pacman -F`
+#[derive(Debug, Clone, Default)]
+pub struct OwnerDialog {
+    files_db: Option<Arc<FilesDb>>,
+    query: String,
+    editing: bool,
+    owners: Vec<Owner>,
+    ///paths being searched in the background
+    searching: Option<Vec<PathBuf>>,
+    ///packages skipped by the last search
+    unreadable: Vec<String>,
+    error: Option<String>,
+    current: usize,
+}
+
+impl OwnerDialog {
+    pub fn new() -> Self {
+        Self {
+            editing: true,
+            ..Default::default()
+        }
+    }
+
+    pub fn for_repositories(files_db: Arc<FilesDb>) -> Self {
+        Self {
+            files_db: Some(files_db),
+            ..Self::new()
+        }
+    }
+
+    /// Repository searches are done right away, installed ones return the job to run
+    fn search(&mut self) -> Option<BackgroundJob> {
+        self.owners.clear();
+        self.unreadable.clear();
+        self.current = 0;
+        self.error = None;
+        if let Some(files_db) = &self.files_db {
+            self.owners = files_db
+                .search(&self.query)
+                .into_iter()
+                .map(|m| Owner {
+                    path: m.path,
+                    package: m.name,
+                    repository: Some(m.repository),
+                })
+                .collect();
+            if self.owners.is_empty() {
+                self.error = Some(format!("No repository package has {}", self.query.trim()));
+            } else {
+                self.editing = false;
+            }
+            return None;
+        }
+        let cwd = std::env::current_dir().unwrap_or_default();
+        let path_var = std::env::var("PATH").ok();
+        let paths = query_paths(&self.query, path_var.as_deref(), &cwd);
+        if paths.is_empty() {
+            self.error = Some(format!("{} not found in $PATH", self.query.trim()));
+            return None;
+        }
+        self.searching = Some(paths.clone());
+        Some(BackgroundJob::FindOwners(paths))
+    }
+
+    /// Show the owners found for `paths`, results of an older search are dropped
+    pub fn set_owners(&mut self, paths: &[PathBuf], scan: OwnerScan) {
+        if self.searching.as_deref() != Some(paths) {
+            return;
+        }
+        self.searching = None;
+        self.unreadable = scan.unreadable;
+        if scan.owners.is_empty() {
+            let paths: Vec<String> = paths.iter().map(|p| p.display().to_string()).collect();
+            self.error = Some(format!("No package owns {}", paths.join(" or ")));
+        } else {
+            self.owners = scan.owners;
+            self.editing = false;
+        }
+    }
+
+    /// Returns Some when the dialog should close, jumping to the chosen package.
+    /// A search of the installed packages returns `EventResult::Background` and stays open
+    pub fn handle_key_event(&mut self, key: &KeyEvent) -> Option<EventResult> {
+        if self.editing {
+            match key.code {
+                KeyCode::Esc => return Some(EventResult::None),
+                KeyCode::Enter => return self.search().map(EventResult::Background),
+                KeyCode::Backspace => {
+                    self.query.pop();
+                }
+                KeyCode::Char(c) => self.query.push(c),
+                _ => {}
+            }
+            return None;
+        }
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => return Some(EventResult::None),
+            KeyCode::Up | KeyCode::Char('k') => self.current = self.current.saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.current = (self.current + 1).min(self.owners.len().saturating_sub(1))
+            }
+            KeyCode::Enter => {
+                if let Some(owner) = self.owners.get(self.current) {
+                    return Some(match owner.repository {
+                        Some(_) => EventResult::GotoPackage(owner.package.clone()),
+                        None => EventResult::GotoInstalled(owner.package.clone()),
+                    });
+                }
+            }
+            KeyCode::Char('/') | KeyCode::Backspace => self.editing = true,
+            _ => {}
+        }
+        None
+    }
+}
+
+impl Widget for OwnerDialog {
+    fn render(self, area: Rect, buf: &mut ratatui::buffer::Buffer) {
+        let cursor = if self.editing { "█" } else { "" };
+        let mut lines = vec![
+            Line::from(vec![
+                Span::raw("Path or command: "),
+                Span::raw(format!("{}{cursor}", self.query)).fg(Color::Yellow),
+            ]),
+            Line::from(""),
+        ];
+        if self.searching.is_some() {
+            lines.push(Line::from("Searching the installed packages…"));
+        }
+        if let Some(error) = &self.error {
+            lines.push(Line::from(error.clone()).fg(Color::Red));
+        }
+        if let Some(first) = self.unreadable.first() {
+            let text = format!("{} packages skipped, {first}", self.unreadable.len());
+            lines.push(Line::from(text).fg(Color::DarkGray));
+        }
+        for (i, owner) in self.owners.iter().enumerate() {
+            let text = match &owner.repository {
+                Some(repo) => format!("{} is in {repo}/{}", owner.path, owner.package),
+                None => format!("{} is owned by {}", owner.path, owner.package),
+            };
+            if i == self.current {
+                lines.push(Line::from(Span::styled(
+                    text,
+                    Style::new().black().on_yellow(),
+                )));
+            } else {
+                lines.push(Line::from(text));
+            }
+        }
+        lines.push(Line::from(""));
+        let help = if self.editing {
+            "Enter: Search  Esc: Cancel"
+        } else {
+            "Enter: Go to package  /: New search  Esc: Close"
+        };
+        lines.push(Line::from(help).fg(Color::Yellow));
+        let title = if self.files_db.is_some() {
+            "Which repository package has a file"
+        } else {
+            "Which package owns a file"
+        };
+        render_dialog(title, lines, area, buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::crossterm::event::KeyModifiers;
+
+    #[test]
+    fn test_owner_search() {
+        let mut dialog = OwnerDialog::new();
+        for c in "/usr/bin/sh".chars() {
+            dialog.handle_key_event(&KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE));
+        }
+        let res = dialog.handle_key_event(&KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+        let Some(EventResult::Background(BackgroundJob::FindOwners(paths))) = res else {
+            panic!("the installed packages are searched in the background");
+        };
+        assert!(paths.contains(&PathBuf::from("/usr/bin/sh")));
+
+        let owner = Owner {
+            path: "/usr/bin/sh".to_string(),
+            package: "bash".to_string(),
+            repository: None,
+        };
+        let scan = OwnerScan {
+            owners: vec![owner.clone()],
+            unreadable: vec!["glibc: unreadable".to_string()],
+        };
+        //an older search is ignored
+        dialog.set_owners(&[PathBuf::from("/usr/bin/bash")], scan.clone());
+        assert!(dialog.owners.is_empty());
+        dialog.set_owners(&paths, scan);
+        assert_eq!(dialog.owners, vec![owner]);
+        assert_eq!(dialog.unreadable.len(), 1);
+        assert!(!dialog.editing);
+    }
+}
@@ -1,50 +1,90 @@
 use ratatui::crossterm::event::{KeyCode, KeyEvent};
-use ratatui::{layout::Constraint, style::Color, widgets::Widget};
+use ratatui::{
+    layout::{Constraint, Layout},
+    style::Color,
+    widgets::Widget,
+};
+
+use std::sync::Arc;
 
 use crate::{
+    db::files::FilesDb,
+    error::AppError,
+    graph::PackageGraph,
+    plan::InstallPlan,
+    source::{PackageSource, pacman::PacmanSource},
     structs::{
-        event::{EventCommand, EventResult},
+        event::{BackgroundJob, EventResult},
         package::Package,
     },
     widgets::{
         Commands, CurrentPackage,
-        table::{TableRow, TableWidget},
+        installdialog::InstallDialog,
+        ownerdialog::OwnerDialog,
+        table::{TableFocus, TableRow, TableWidget},
     },
 };
 
 #[derive(Debug, Clone)]
 pub struct PackagesWidget {
-    data: Vec<Package>,
+    source: Arc<dyn PackageSource>,
+    graph: Arc<PackageGraph>,
+    ///read in the background on first use, the `.files` databases are large
+    files_db: Option<Result<Arc<FilesDb>, String>>,
+    files_db_loading: bool,
+    ///open the find dialog once the files databases are read
+    find_pending: bool,
     table: TableWidget,
+    files: TableWidget,
+    show_files: bool,
+    files_focused: bool,
+    install_dialog: Option<InstallDialog>,
+    owner_dialog: Option<OwnerDialog>,
 }
 impl Default for PackagesWidget {
     fn default() -> Self {
+        Self::new(Arc::new(PacmanSource::default()))
+    }
+}
+
+impl PackagesWidget {
+    pub fn new(source: Arc<dyn PackageSource>) -> Self {
         Self {
-            data: vec![],
+            source,
+            graph: Arc::default(),
+            files_db: None,
+            files_db_loading: false,
+            find_pending: false,
             table: TableWidget::new(
-                &["Name", "Installed", "Info"],
+                &["Name", "Repo", "Installed", "Info"],
                 vec![
                     Constraint::Percentage(30),
+                    Constraint::Length(10),
                     Constraint::Length(19),
                     Constraint::Percentage(70),
                 ],
             ),
+            files: TableWidget::new(&[], vec![Constraint::Percentage(100)]).with_no_focus(),
+            show_files: false,
+            files_focused: false,
+            install_dialog: None,
+            owner_dialog: None,
         }
     }
-}
 
-impl PackagesWidget {
-    pub fn set_data(&mut self, data: &[Package]) {
-        if data == self.data {
+    pub fn set_data(&mut self, graph: Arc<PackageGraph>) {
+        if Arc::ptr_eq(&graph, &self.graph) {
             return;
         }
-        self.data = data.to_vec();
+        self.graph = graph;
         self.table.set_data(
-            self.data
+            self.graph
+                .packages()
                 .iter()
                 .map(|pkg| {
                     TableRow::new(vec![
                         pkg.name.clone(),
+                        pkg.repository.clone().unwrap_or_default(),
                         pkg.installed.clone().unwrap_or_default(),
                         pkg.description.clone(),
                     ])
@@ -57,6 +97,94 @@ impl PackagesWidget {
                 .collect(),
         );
         self.update_title();
+        self.update_files();
+    }
+    ///ask the main loop to read the files databases, once
+    fn request_files_db(&mut self) -> EventResult {
+        if self.files_db.is_some() || self.files_db_loading {
+            return EventResult::None;
+        }
+        self.files_db_loading = true;
+        EventResult::Background(BackgroundJob::FilesDb)
+    }
+    /// The files databases were read, returns a message if the find dialog could not open
+    pub fn set_files_db(&mut self, res: Result<FilesDb, AppError>) -> Option<String> {
+        self.files_db_loading = false;
+        self.files_db = Some(match res {
+            Ok(db) if db.is_empty() => Err("No .files databases, run pacman -Fy".to_string()),
+            Ok(db) => Ok(Arc::new(db)),
+            Err(e) => Err(e.to_string()),
+        });
+        self.update_files();
+        if !std::mem::take(&mut self.find_pending) {
+            return None;
+        }
+        self.open_find()
+    }
+    fn open_find(&mut self) -> Option<String> {
+        match self.files_db.clone()? {
+            Ok(files_db) => {
+                self.owner_dialog = Some(OwnerDialog::for_repositories(files_db));
+                None
+            }
+            Err(e) => Some(e),
+        }
+    }
+    ///installed packages list their own files, others come from the files database
+    fn update_files(&mut self) {
+        if !self.show_files {
+            return;
+        }
+        let Some(pack) = self.current_package().cloned() else {
+            self.files.set_data(vec![]);
+            self.files.set_title("");
+            return;
+        };
+        let files = if pack.installed.is_some() {
+            self.source.files(&pack).map_err(|e| e.to_string())
+        } else {
+            match &self.files_db {
+                Some(Ok(db)) => Ok(db
+                    .files(&pack.name)
+                    .map(<[String]>::to_vec)
+                    .unwrap_or_default()),
+                Some(Err(e)) => Err(e.clone()),
+                None => Err("Reading the files databases…".to_string()),
+            }
+        };
+        match files {
+            Ok(files) => {
+                let rows: Vec<TableRow> = files
+                    .iter()
+                    .filter(|p| !p.ends_with('/'))
+                    .map(|p| TableRow::new(vec![p.clone()]))
+                    .collect();
+                self.files.set_title(&format!("{} files", rows.len()));
+                self.files.set_data(rows);
+            }
+            Err(e) => {
+                self.files.set_title(&e);
+                self.files.set_data(vec![]);
+            }
+        }
+    }
+    fn set_files_focus(&mut self, focused: bool) {
+        self.files_focused = focused && self.show_files;
+        if self.files_focused {
+            self.table.focus(TableFocus::UnfocusedDimmed);
+            self.files.focus(TableFocus::Focused);
+        } else {
+            self.table.focus(TableFocus::Focused);
+            self.files.focus(TableFocus::Unfocused);
+        }
+    }
+    pub(crate) fn goto_package_by_name(&mut self, name: &str) {
+        self.set_files_focus(false);
+        self.table.clear_search();
+        let index = self.table.rows().iter().position(|r| r.cells[0] == name);
+        self.table.set_current(index);
+        self.update_title();
+        self.update_files();
     }
     fn update_title(&mut self) {
         let filtered = self.table.rows().len();
@@ -64,7 +192,7 @@ impl PackagesWidget {
             .table
             .rows()
             .iter()
-            .filter(|r| !r.cells[1].is_empty())
+            .filter(|r| !r.cells[2].is_empty())
             .count();
         self.table
             .set_title(&format!("{} Packages ({} installed)", filtered, installed));
@@ -72,29 +200,109 @@ impl PackagesWidget {
 }
 impl Widget for PackagesWidget {
     fn render(self, area: ratatui::layout::Rect, buf: &mut ratatui::buffer::Buffer) {
-        self.table.render(area, buf);
+        let lower = if self.show_files {
+            ((area.height as f32 * 0.4) as u16).max(5)
+        } else {
+            0
+        };
+        let verti =
+            Layout::vertical(vec![Constraint::Min(5), Constraint::Length(lower)]).split(area);
+        self.table.render(verti[0], buf);
+        if self.show_files {
+            self.files.render(verti[1], buf);
+        }
+        if let Some(dialog) = self.install_dialog {
+            dialog.render(area, buf);
+        }
+        if let Some(dialog) = self.owner_dialog {
+            dialog.render(area, buf);
+        }
     }
 }
 impl Commands for PackagesWidget {
     fn command_descriptions(&self) -> Vec<(&str, &str, &str)> {
-        vec![("u", "Update/Install package", "Update/Install")]
+        vec![
+            (
+                "u",
+                "Update/Install package (with preview)",
+                "Update/Install",
+            ),
+            ("p", "View files in package", "Files"),
+            (
+                "F",
+                "Find repository packages containing a file",
+                "Find file",
+            ),
+            ("P", "Focus files pane", ""),
+        ]
     }
 
     fn handle_key_event(&mut self, key: &KeyEvent) -> Option<EventResult> {
-        if self.table.handle_key_event(key) {
+        //dialog takes all keys while open
+        if let Some(dialog) = self.install_dialog.as_mut() {
+            let res = dialog.handle_key_event(key);
+            if res.is_some() {
+                self.install_dialog = None;
+            }
+            return Some(res.unwrap_or(EventResult::None));
+        }
+        if let Some(dialog) = self.owner_dialog.as_mut() {
+            let res = dialog.handle_key_event(key);
+            if res.is_some() {
+                self.owner_dialog = None;
+            }
+            return Some(res.unwrap_or(EventResult::None));
+        }
+        if self.files_focused {
+            if self.files.handle_key_event(key) {
+                return Some(EventResult::None);
+            }
+        } else if self.table.handle_key_event(key) {
             self.update_title(); //may have filtered
+            self.update_files();
             return Some(EventResult::None);
         }
+        match key.code {
+            KeyCode::Char('p') => {
+                self.show_files = !self.show_files;
+                self.set_files_focus(self.files_focused);
+                self.update_files();
+                return Some(match self.show_files {
+                    true => self.request_files_db(),
+                    false => EventResult::None,
+                });
+            }
+            KeyCode::Char('P') => {
+                self.set_files_focus(!self.files_focused);
+                return Some(EventResult::None);
+            }
+            KeyCode::Char('F') => {
+                if self.files_db.is_none() {
+                    self.find_pending = true;
+                    return Some(self.request_files_db());
+                }
+                return Some(match self.open_find() {
+                    Some(e) => EventResult::Message(e),
+                    None => EventResult::None,
+                });
+            }
+            _ => {}
+        }
         if let KeyCode::Char('u') = key.code {
-            let packs = self
+            let mut packs = self
                 .table
                 .get_selected()
                 .iter()
                 .map(|&i| i.cells[0].clone())
                 .collect::<Vec<_>>();
-            return Some(EventResult::Command(EventCommand::InstallOrUpdateSelected(
-                packs,
-            )));
+            if packs.is_empty()
+                && let Some(pack) = self.current_package()
+            {
+                packs.push(pack.name.clone());
+            }
+            let plan = InstallPlan::new(&packs, &self.graph);
+            self.install_dialog = Some(InstallDialog::new(plan));
+            return Some(EventResult::None);
         }
 
         None
@@ -105,6 +313,32 @@ impl CurrentPackage for PackagesWidget {
     fn current_package(&self) -> Option<&Package> {
         self.table
             .get_current()
-            .and_then(|a| self.data.iter().find(|b| b.name == a.cells[0]))
+            .and_then(|a| self.graph.get(&a.cells[0]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{pman::load_packages, source::fixture::FixtureSource};
+    use std::path::Path;
+
+    #[test]
+    fn test_files_db() {
+        let source: Arc<dyn PackageSource> = Arc::new(FixtureSource::new(
+            &Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures"),
+        ));
+        let graph = load_packages(source.as_ref(), &|_| {}, &|_| {}).unwrap();
+        let mut widget = PackagesWidget::new(source.clone());
+        widget.set_data(Arc::new(graph));
+
+        //the files databases are read in the background, once
+        let key = |c| KeyEvent::from(KeyCode::Char(c));
+        let res = widget.handle_key_event(&key('F'));
+        assert!(res == Some(EventResult::Background(BackgroundJob::FilesDb)));
+        let res = widget.handle_key_event(&key('p'));
+        assert!(res == Some(EventResult::None));
+        assert_eq!(widget.set_files_db(source.sync_files()), None);
+        assert!(widget.owner_dialog.is_some());
     }
 }
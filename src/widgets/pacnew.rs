@@ -0,0 +1,303 @@
+use ratatui::{
+    crossterm::event::{KeyCode, KeyEvent},
+    layout::{Constraint, Layout, Rect},
+    style::Color,
+    widgets::Widget,
+};
+
+use std::{path::PathBuf, sync::Arc};
+
+use crate::{
+    error::AppError,
+    graph::PackageGraph,
+    pacnew::{PacFile, PacFileKind, PacfileScan},
+    structs::{
+        event::{BackgroundJob, EventCommand, EventResult},
+        package::Package,
+    },
+    widgets::{
+        Commands, CurrentPackage,
+        confirmdialog::ConfirmDialog,
+        table::{Sort, TableFocus, TableRow, TableWidget},
+    },
+};
+
+/// `.pacnew` and `.pacsave` files of installed packages, with a diff against the live file.
+/// Found from the backup entries of installed packages, looked up again on reload
+#[derive(Debug, Clone)]
+pub struct PacnewWidget {
+    graph: Arc<PackageGraph>,
+    pacfiles: Vec<PacFile>,
+    ///packages whose backup files could not be read
+    unreadable: Vec<String>,
+    ///the tab was opened, so a reload looks for pacfiles again
+    scanned: bool,
+    ///reviewed and kept as they are, for this session
+    kept: Vec<PathBuf>,
+    list: TableWidget,
+    diff: TableWidget,
+    diff_focused: bool,
+    confirm: Option<ConfirmDialog>,
+}
+
+impl Default for PacnewWidget {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PacnewWidget {
+    pub fn new() -> Self {
+        Self {
+            graph: Arc::default(),
+            pacfiles: vec![],
+            unreadable: vec![],
+            scanned: false,
+            kept: vec![],
+            list: TableWidget::new(
+                &["Package", "File", "Type", "Status"],
+                vec![
+                    Constraint::Percentage(25),
+                    Constraint::Percentage(60),
+                    Constraint::Length(8),
+                    Constraint::Length(6),
+                ],
+            ),
+            diff: TableWidget::new(&[], vec![Constraint::Percentage(100)])
+                .with_sort(0, Sort::None)
+                .with_no_focus(),
+            diff_focused: false,
+            confirm: None,
+        }
+    }
+
+    /// Returns the job looking for pacfiles again once the tab was opened
+    pub fn set_data(&mut self, graph: Arc<PackageGraph>) -> Option<BackgroundJob> {
+        self.graph = graph;
+        self.scanned.then(|| self.refresh())
+    }
+
+    /// Look for pacfiles again, e.g. after one was replaced
+    pub fn refresh(&mut self) -> BackgroundJob {
+        self.scanned = true;
+        self.list.set_title("Looking for pacnew files…");
+        BackgroundJob::Pacfiles
+    }
+
+    /// Show the pacfiles found in the background, staying on the current one
+    pub fn set_pacfiles(&mut self, res: Result<PacfileScan, AppError>) {
+        let current = self.list.get_current().map(|r| r.cells[1].clone());
+        match res {
+            Ok(scan) => {
+                self.pacfiles = scan.pacfiles;
+                self.unreadable = scan.unreadable;
+                self.kept
+                    .retain(|k| self.pacfiles.iter().any(|p| p.path == *k));
+                self.update_rows(current);
+            }
+            Err(e) => {
+                self.pacfiles.clear();
+                self.unreadable.clear();
+                self.update_rows(None);
+                self.list
+                    .set_title(&format!("Error finding pacnew files: {e}"));
+            }
+        }
+    }
+
+    ///rebuild the list, staying on the row with the `current` path if it is still there
+    fn update_rows(&mut self, current: Option<String>) {
+        let rows: Vec<TableRow> = self
+            .pacfiles
+            .iter()
+            .map(|p| {
+                let kept = self.kept.contains(&p.path);
+                let color = match p.kind {
+                    _ if kept => Color::DarkGray,
+                    PacFileKind::Pacnew => Color::Yellow,
+                    PacFileKind::Pacsave => Color::Cyan,
+                };
+                TableRow::new(vec![
+                    p.package.clone(),
+                    p.path.to_string_lossy().to_string(),
+                    p.kind.to_string(),
+                    if kept { "kept" } else { "" }.to_string(),
+                ])
+                .with_highlight(Some(color))
+            })
+            .collect();
+        self.list.set_data(rows);
+        let reviewed = self.kept.len();
+        let unreadable = match self.unreadable.first() {
+            Some(first) => format!(", {} packages skipped, {first}", self.unreadable.len()),
+            None => String::new(),
+        };
+        self.list.set_title(&format!(
+            "{} pacnew/pacsave files ({reviewed} kept){unreadable}",
+            self.pacfiles.len()
+        ));
+        if let Some(current) = current {
+            let index = self.list.rows().iter().position(|r| r.cells[1] == current);
+            if index.is_some() {
+                self.list.set_current(index);
+            }
+        }
+        self.update_diff();
+    }
+
+    fn current(&self) -> Option<&PacFile> {
+        let row = self.list.get_current()?;
+        self.pacfiles
+            .iter()
+            .find(|p| p.path.to_string_lossy() == row.cells[1])
+    }
+
+    fn update_diff(&mut self) {
+        let Some(pacfile) = self.current() else {
+            self.diff.set_title("Diff");
+            self.diff.set_data(vec![]);
+            return;
+        };
+        let (title, rows) = match pacfile.diff() {
+            Ok(lines) if lines.is_empty() => ("Diff: identical".to_string(), vec![]),
+            Ok(lines) => {
+                let changed = lines
+                    .iter()
+                    .skip(2)
+                    .filter(|l| l.starts_with('+') || l.starts_with('-'))
+                    .count();
+                let rows = lines
+                    .into_iter()
+                    .map(|l| {
+                        let color = match l.chars().next() {
+                            _ if l.starts_with("---") || l.starts_with("+++") => Color::White,
+                            Some('+') => Color::Green,
+                            Some('-') => Color::Red,
+                            Some('@') => Color::Cyan,
+                            _ => Color::Gray,
+                        };
+                        TableRow::new(vec![l]).with_highlight(Some(color))
+                    })
+                    .collect();
+                (format!("Diff: {changed} lines changed"), rows)
+            }
+            Err(e) => (format!("Diff: {e}"), vec![]),
+        };
+        self.diff.set_title(&title);
+        self.diff.set_data(rows);
+    }
+
+    fn set_diff_focus(&mut self, focused: bool) {
+        self.diff_focused = focused;
+        if focused {
+            self.list.focus(TableFocus::UnfocusedDimmed);
+            self.diff.focus(TableFocus::Focused);
+        } else {
+            self.list.focus(TableFocus::Focused);
+            self.diff.focus(TableFocus::Unfocused);
+        }
+    }
+
+    ///mark as reviewed and move on to the next one
+    fn keep_current(&mut self) {
+        let Some(path) = self.current().map(|p| p.path.clone()) else {
+            return;
+        };
+        if !self.kept.contains(&path) {
+            self.kept.push(path.clone());
+        }
+        let rows = self.list.rows();
+        let next = rows
+            .iter()
+            .position(|r| r.cells[1] == path.to_string_lossy())
+            .and_then(|i| rows.get(i + 1))
+            .map(|r| r.cells[1].clone())
+            .unwrap_or_else(|| path.to_string_lossy().to_string());
+        self.update_rows(Some(next));
+    }
+}
+
+impl Widget for PacnewWidget {
+    fn render(self, area: Rect, buf: &mut ratatui::buffer::Buffer) {
+        let areas = Layout::horizontal([Constraint::Percentage(45), Constraint::Percentage(55)])
+            .split(area);
+        self.list.render(areas[0], buf);
+        self.diff.render(areas[1], buf);
+        if let Some(dialog) = self.confirm {
+            dialog.render(area, buf);
+        }
+    }
+}
+
+impl Commands for PacnewWidget {
+    fn command_descriptions(&self) -> Vec<(&str, &str, &str)> {
+        vec![
+            ("K", "Keep both files, mark as reviewed", "Keep"),
+            (
+                "r",
+                "Replace the live file with the pacnew/pacsave",
+                "Replace",
+            ),
+            ("d", "Delete the pacnew/pacsave", "Delete"),
+            ("Enter", "Go to owning package", "Package"),
+            ("←/h →/l", "Focus list or diff", ""),
+        ]
+    }
+
+    fn handle_key_event(&mut self, key: &KeyEvent) -> Option<EventResult> {
+        //confirmation takes all keys while open
+        if let Some(dialog) = &self.confirm {
+            let Some(res) = dialog.handle_key_event(key) else {
+                return Some(EventResult::None);
+            };
+            self.confirm = None;
+            return Some(res);
+        }
+        if self.diff_focused {
+            if self.diff.handle_key_event(key) {
+                return Some(EventResult::None);
+            }
+        } else if self.list.handle_key_event(key) {
+            self.update_diff();
+            return Some(EventResult::None);
+        }
+
+        match key.code {
+            KeyCode::Left | KeyCode::Char('h') => self.set_diff_focus(false),
+            KeyCode::Right | KeyCode::Char('l') => self.set_diff_focus(true),
+            KeyCode::Char('K') => self.keep_current(),
+            KeyCode::Char('r') => {
+                if let Some(p) = self.current() {
+                    let question =
+                        format!("Replace {} with {}?", p.live.display(), p.path.display());
+                    self.confirm = Some(ConfirmDialog::new(
+                        EventCommand::ReplaceWithPacfile(p.path.clone(), p.live.clone()),
+                        question,
+                    ));
+                }
+            }
+            KeyCode::Char('d') => {
+                if let Some(p) = self.current() {
+                    let question = format!("Delete {}?", p.path.display());
+                    self.confirm = Some(ConfirmDialog::new(
+                        EventCommand::DeletePacfile(p.path.clone()),
+                        question,
+                    ));
+                }
+            }
+            KeyCode::Enter => {
+                if let Some(p) = self.current() {
+                    return Some(EventResult::GotoInstalled(p.package.clone()));
+                }
+            }
+            _ => return None,
+        }
+        Some(EventResult::None)
+    }
+}
+
+impl CurrentPackage for PacnewWidget {
+    fn current_package(&self) -> Option<&Package> {
+        self.current().and_then(|p| self.graph.get(&p.package))
+    }
+}
@@ -0,0 +1,116 @@
+use ratatui::{
+    crossterm::event::{KeyCode, KeyEvent},
+    layout::Rect,
+    style::{Color, Style, Stylize},
+    text::{Line, Span},
+    widgets::Widget,
+};
+
+use crate::{
+    plan::{RemovalPlan, RemoveMode},
+    structs::event::{EventCommand, EventResult},
+    utils::human_size,
+    widgets::render_dialog,
+};
+
+/// Shows what a removal would do before running it, and lets the user pick the mode
+#[derive(Debug, Clone)]
+pub struct RemoveDialog {
+    plan: RemovalPlan,
+    mode: RemoveMode,
+}
+
+impl RemoveDialog {
+    pub fn new(plan: RemovalPlan) -> Self {
+        Self {
+            plan,
+            mode: RemoveMode::default(),
+        }
+    }
+
+    fn cycle_mode(&mut self, change: isize) {
+        let modes = RemoveMode::values();
+        let pos = modes.iter().position(|m| *m == self.mode).unwrap_or(0) as isize;
+        let new = (pos + change).rem_euclid(modes.len() as isize) as usize;
+        self.mode = modes[new];
+    }
+
+    /// Returns Some when the dialog should close, with the command to run if confirmed
+    pub fn handle_key_event(&mut self, key: &KeyEvent) -> Option<EventResult> {
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => return Some(EventResult::None),
+            KeyCode::Enter => {
+                return Some(EventResult::Command(EventCommand::RemoveSelected(
+                    self.plan.targets.clone(),
+                    self.mode,
+                )));
+            }
+            KeyCode::Left | KeyCode::Char('h') | KeyCode::BackTab => self.cycle_mode(-1),
+            KeyCode::Right | KeyCode::Char('l') | KeyCode::Tab => self.cycle_mode(1),
+            KeyCode::Char(c @ '1'..='4') => {
+                self.mode = RemoveMode::values()[c as usize - '1' as usize];
+            }
+            _ => {}
+        }
+        None
+    }
+}
+
+impl Widget for RemoveDialog {
+    fn render(self, area: Rect, buf: &mut ratatui::buffer::Buffer) {
+        let mut modes = vec![Span::raw("Mode: ")];
+        for (i, mode) in RemoveMode::values().iter().enumerate() {
+            let text = format!(" {} {} ", i + 1, mode.flag());
+            if *mode == self.mode {
+                modes.push(Span::styled(text, Style::new().black().on_yellow()));
+            } else {
+                modes.push(Span::raw(text));
+            }
+        }
+
+        let removed = self.plan.removed(self.mode);
+        let extra = removed
+            .iter()
+            .skip(self.plan.targets.len())
+            .map(|s| s.as_str())
+            .collect::<Vec<_>>();
+        let broken = self.plan.broken(self.mode);
+
+        let mut lines = vec![
+            Line::from(modes),
+            Line::from(""),
+            Line::from(format!("Remove: {}", self.plan.targets.join(" "))),
+        ];
+        if !extra.is_empty() {
+            lines.push(Line::from(format!(
+                "Also removes {}: {}",
+                extra.len(),
+                extra.join(" ")
+            )));
+        }
+        if !broken.is_empty() {
+            lines.push(Line::from(""));
+            lines.push(Line::from(format!("Breaks {}:", broken.len())).fg(Color::Red));
+            lines.extend(
+                broken
+                    .iter()
+                    .map(|(pack, dep)| Line::from(format!("  {pack} needs {dep}")).fg(Color::Red)),
+            );
+        }
+        lines.push(Line::from(""));
+        lines.push(Line::from(format!(
+            "{} packages, frees {}",
+            removed.len(),
+            human_size(self.plan.freed(self.mode))
+        )));
+        lines.push(Line::from(""));
+        lines.push(Line::from("Enter: Run  Esc: Cancel  ←/→ 1-4: Mode").fg(Color::Yellow));
+
+        render_dialog(
+            &format!("Remove with pacman {}", self.mode),
+            lines,
+            area,
+            buf,
+        );
+    }
+}
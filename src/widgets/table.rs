@@ -36,6 +36,8 @@ pub enum Sort {
     #[default]
     Asc,
     Desc,
+    ///keep the order the rows were given in, until a column is chosen
+    None,
 }
 
 #[derive(Debug, Clone, PartialEq, Copy)]
@@ -82,6 +84,12 @@ impl TableWidget {
             searching: false,
         }
     }
+    pub fn with_sort(self, column_index: usize, sort: Sort) -> Self {
+        Self {
+            sort_by: (column_index, sort),
+            ..self
+        }
+    }
     pub fn with_no_focus(self) -> Self {
         Self {
             focus_type: TableFocus::Unfocused,
@@ -137,13 +145,11 @@ impl TableWidget {
                 }
             }
             KeyCode::Char('/') => self.searching = true,
-            KeyCode::Char('a') => {
-                if key.modifiers.contains(KeyModifiers::CONTROL) {
-                    if self.get_selected().len() == self.filtered.len() {
-                        self.clear_selection();
-                    } else {
-                        self.select_all();
-                    }
+            KeyCode::Char('a') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                if self.get_selected().len() == self.filtered.len() {
+                    self.clear_selection();
+                } else {
+                    self.select_all();
                 }
             }
 
@@ -254,6 +260,7 @@ impl TableWidget {
                     a.cells[sort_col].replace(",", "").trim(),
                 )
             }),
+            Sort::None => {}
         }
     }
 
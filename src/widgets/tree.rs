@@ -0,0 +1,249 @@
+use std::collections::HashSet;
+
+use ratatui::{
+    crossterm::event::{KeyCode, KeyEvent},
+    layout::Rect,
+    style::{Color, Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, List, ListState, StatefulWidget, Widget},
+};
+
+use crate::{
+    graph::{TreeMark, TreeNode},
+    widgets::table::TableFocus,
+};
+
+/// A collapsible tree, used next to `TableWidget` for recursive dependencies.
+/// Nodes are addressed by the child indices leading to them from the roots
+#[derive(Debug, Clone, Default)]
+pub struct TreeWidget {
+    roots: Vec<TreeNode>,
+    expanded: HashSet<Vec<usize>>,
+    list_state: ListState,
+    title: Option<String>,
+    focus_type: TableFocus,
+}
+
+/// A node as currently visible, with the path to it
+struct VisibleNode<'a> {
+    path: Vec<usize>,
+    node: &'a TreeNode,
+}
+
+impl TreeWidget {
+    pub fn new() -> Self {
+        Self {
+            focus_type: TableFocus::Unfocused,
+            ..Default::default()
+        }
+    }
+
+    /// Replace the trees, the expanded nodes are kept if the trees did not change
+    pub fn set_roots(&mut self, roots: Vec<TreeNode>) {
+        if roots == self.roots {
+            return;
+        }
+        //roots start expanded
+        self.expanded = (0..roots.len()).map(|i| vec![i]).collect();
+        self.roots = roots;
+        self.list_state
+            .select(if self.roots.is_empty() { None } else { Some(0) });
+    }
+
+    pub fn set_title(&mut self, title: &str) {
+        self.title = Some(title.to_string());
+    }
+
+    pub fn focus(&mut self, focus: TableFocus) {
+        self.focus_type = focus;
+    }
+
+    fn node_at(&self, path: &[usize]) -> Option<&TreeNode> {
+        let (first, rest) = path.split_first()?;
+        let mut node = self.roots.get(*first)?;
+        for i in rest {
+            node = node.children.get(*i)?;
+        }
+        Some(node)
+    }
+
+    fn visible(&self) -> Vec<VisibleNode<'_>> {
+        let mut out = vec![];
+        let mut stack: Vec<(Vec<usize>, &TreeNode)> = self
+            .roots
+            .iter()
+            .enumerate()
+            .rev()
+            .map(|(i, n)| (vec![i], n))
+            .collect();
+        while let Some((path, node)) = stack.pop() {
+            if self.expanded.contains(&path) {
+                for (i, child) in node.children.iter().enumerate().rev() {
+                    let mut child_path = path.clone();
+                    child_path.push(i);
+                    stack.push((child_path, child));
+                }
+            }
+            out.push(VisibleNode { path, node });
+        }
+        out
+    }
+
+    fn current_path(&self) -> Option<Vec<usize>> {
+        let i = self.list_state.selected()?;
+        self.visible().into_iter().nth(i).map(|v| v.path)
+    }
+
+    /// The node under the cursor
+    pub fn get_current(&self) -> Option<&TreeNode> {
+        self.node_at(&self.current_path()?)
+    }
+
+    fn select_path(&mut self, path: &[usize]) {
+        let index = self.visible().iter().position(|v| v.path == path);
+        self.list_state.select(index);
+    }
+
+    fn safe_move(&mut self, change: isize) {
+        let len = self.visible().len();
+        if len == 0 {
+            return;
+        }
+        let current = self.list_state.selected().unwrap_or(0) as isize;
+        let new = current.saturating_add(change).clamp(0, len as isize - 1);
+        self.list_state.select(Some(new as usize));
+    }
+
+    fn expand_all(&mut self, path: Vec<usize>) {
+        let Some(node) = self.node_at(&path) else {
+            return;
+        };
+        let children = node.children.len();
+        for i in 0..children {
+            let mut child = path.clone();
+            child.push(i);
+            self.expand_all(child);
+        }
+        self.expanded.insert(path);
+    }
+
+    ///return true if event was handled and should not be processed further
+    pub(crate) fn handle_key_event(&mut self, key: &KeyEvent) -> bool {
+        let Some(path) = self.current_path() else {
+            return false;
+        };
+        let has_children = self.node_at(&path).is_some_and(|n| !n.children.is_empty());
+        match key.code {
+            KeyCode::Up | KeyCode::Char('k') => self.safe_move(-1),
+            KeyCode::Down | KeyCode::Char('j') => self.safe_move(1),
+            KeyCode::Home => self.safe_move(isize::MIN),
+            KeyCode::End => self.safe_move(isize::MAX),
+            KeyCode::PageUp => self.safe_move(-10),
+            KeyCode::PageDown => self.safe_move(10),
+            KeyCode::Right | KeyCode::Char('+') if has_children => {
+                self.expanded.insert(path);
+            }
+            KeyCode::Char(' ') if has_children => {
+                if !self.expanded.remove(&path) {
+                    self.expanded.insert(path);
+                }
+            }
+            KeyCode::Char('*') => self.expand_all(path),
+            //collapse, or go to the parent if already collapsed
+            KeyCode::Left | KeyCode::Char('-') => {
+                if !self.expanded.remove(&path) && path.len() > 1 {
+                    self.select_path(&path[..path.len() - 1]);
+                }
+            }
+            _ => return false,
+        }
+        true
+    }
+}
+
+fn node_line(visible: &VisibleNode, expanded: bool) -> Line<'static> {
+    let node = visible.node;
+    let indent = "  ".repeat(visible.path.len() - 1);
+    let marker = match (node.children.is_empty(), expanded) {
+        (true, _) => "  ",
+        (false, true) => "▾ ",
+        (false, false) => "▸ ",
+    };
+    let mut spans = vec![Span::raw(format!("{indent}{marker}{}", node.label))];
+    match node.mark {
+        TreeMark::None => {}
+        TreeMark::Seen => spans.push(Span::raw(" (seen)").fg(Color::DarkGray)),
+        TreeMark::Cycle => spans.push(Span::raw(" (cycle)").fg(Color::Magenta)),
+        TreeMark::Missing => spans[0] = spans[0].clone().fg(Color::Red),
+    }
+    Line::from(spans)
+}
+
+impl Widget for TreeWidget {
+    fn render(mut self, area: Rect, buf: &mut ratatui::buffer::Buffer) {
+        let items: Vec<Line> = self
+            .visible()
+            .iter()
+            .map(|v| node_line(v, self.expanded.contains(&v.path)))
+            .collect();
+        let highlight = match self.focus_type {
+            TableFocus::Focused => Style::new().black().on_yellow(),
+            TableFocus::UnfocusedDimmed => Style::new().black().on_dark_gray(),
+            TableFocus::Unfocused => Style::new(),
+        };
+        let list = List::new(items)
+            .block(Block::bordered().title(self.title.clone().unwrap_or_default()))
+            .highlight_style(highlight);
+        StatefulWidget::render(list, area, buf, &mut self.list_state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(name: &str, children: Vec<TreeNode>) -> TreeNode {
+        TreeNode {
+            name: name.to_string(),
+            label: name.to_string(),
+            mark: TreeMark::None,
+            children,
+        }
+    }
+
+    #[test]
+    fn test_tree_navigation() {
+        let mut tree = TreeWidget::new();
+        tree.set_roots(vec![node(
+            "a",
+            vec![node("b", vec![node("c", vec![])]), node("d", vec![])],
+        )]);
+        let key = |code| KeyEvent::from(code);
+        let names = |tree: &TreeWidget| {
+            tree.visible()
+                .iter()
+                .map(|v| v.node.name.clone())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(names(&tree), vec!["a", "b", "d"]);
+        tree.handle_key_event(&key(KeyCode::Down));
+        tree.handle_key_event(&key(KeyCode::Right));
+        assert_eq!(names(&tree), vec!["a", "b", "c", "d"]);
+
+        //collapsing a leaf goes to its parent
+        tree.handle_key_event(&key(KeyCode::Down));
+        assert_eq!(tree.get_current().unwrap().name, "c");
+        tree.handle_key_event(&key(KeyCode::Left));
+        assert_eq!(tree.get_current().unwrap().name, "b");
+        tree.handle_key_event(&key(KeyCode::Left));
+        assert_eq!(names(&tree), vec!["a", "b", "d"]);
+
+        //same roots keep the state
+        tree.set_roots(tree.roots.clone());
+        assert_eq!(tree.get_current().unwrap().name, "b");
+        tree.handle_key_event(&key(KeyCode::Home));
+        tree.handle_key_event(&key(KeyCode::Char('*')));
+        assert_eq!(names(&tree), vec!["a", "b", "c", "d"]);
+    }
+}
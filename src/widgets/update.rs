@@ -57,7 +57,7 @@ impl UpdateWidget {
         self.filtered = self
             .data
             .iter()
-            .filter(|pkg| pkg.change_type >= self.filter)
+            .filter(|pkg| shown(&pkg.change_type, &self.filter))
             .cloned()
             .collect();
 
@@ -75,13 +75,11 @@ impl UpdateWidget {
                             r.new_version_size.map(thousands).unwrap_or_default()
                         ),
                     ])
-                    .with_highlight(
-                        if r.change_type >= Some(ChangeType::Major) {
-                            Some(Color::Green)
-                        } else {
-                            None
-                        },
-                    )
+                    .with_highlight(match r.change_type {
+                        Some(ChangeType::Downgrade) => Some(Color::Red),
+                        Some(ChangeType::Major | ChangeType::Epoch) => Some(Color::Green),
+                        _ => None,
+                    })
                 })
                 .collect(),
         );
@@ -100,10 +98,10 @@ impl UpdateWidget {
             .iter()
             .map(|(k, v)| format!("{} {}", v, k))
             .collect::<Vec<String>>();
-        let filters = if let Some(f) = &self.filter {
-            format!("Filters: >={:?}", f)
-        } else {
-            String::new()
+        let filters = match &self.filter {
+            Some(ChangeType::Downgrade) => "Filters: Downgrade".to_string(),
+            Some(f) => format!("Filters: >={:?}", f),
+            None => String::new(),
         };
 
         let message = format!(
@@ -119,6 +117,15 @@ impl UpdateWidget {
     }
 }
 
+///`d` shows only downgrades, the other filters that change type and bigger ones, without downgrades
+fn shown(change: &Option<ChangeType>, filter: &Option<ChangeType>) -> bool {
+    match filter {
+        None => true,
+        Some(ChangeType::Downgrade) => change == &Some(ChangeType::Downgrade),
+        Some(_) => change != &Some(ChangeType::Downgrade) && change >= filter,
+    }
+}
+
 impl Widget for UpdateWidget {
     fn render(self, area: ratatui::layout::Rect, buf: &mut ratatui::buffer::Buffer) {
         self.table.render(area, buf);
@@ -132,6 +139,7 @@ impl Commands for UpdateWidget {
             ("U", "Update all packages", "Update All"),
             ("m", "Show major changes and up", "Major"),
             ("n", "Show minor changes and up", "Minor"),
+            ("d", "Show downgrades", "Downgrades"),
             ("a", "Show all changes", "All"),
             ("Enter", "View dependencies", "Dependencies"),
         ]
@@ -159,11 +167,9 @@ impl Commands for UpdateWidget {
             KeyCode::Char('U') => {
                 return Some(EventResult::Command(EventCommand::SyncAndUpdateAll));
             }
-            KeyCode::Char('a') => {
-                if !key.modifiers.contains(KeyModifiers::CONTROL) {
-                    self.filter = None;
-                    self.filter_data();
-                }
+            KeyCode::Char('a') if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.filter = None;
+                self.filter_data();
             }
 
             KeyCode::Char('m') => {
@@ -174,6 +180,10 @@ impl Commands for UpdateWidget {
                 self.filter = Some(ChangeType::Minor);
                 self.filter_data();
             }
+            KeyCode::Char('d') => {
+                self.filter = Some(ChangeType::Downgrade);
+                self.filter_data();
+            }
             KeyCode::Esc => {
                 self.filter = None;
                 self.filter_data();
@@ -199,3 +209,22 @@ impl CurrentPackage for UpdateWidget {
             .and_then(|a| self.filtered.iter().find(|p| p.name == a.cells[0]))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shown() {
+        let major = Some(ChangeType::Major);
+        let minor = Some(ChangeType::Minor);
+        let downgrade = Some(ChangeType::Downgrade);
+        assert!(shown(&downgrade, &None));
+        assert!(shown(&major, &minor));
+        assert!(!shown(&minor, &major));
+        assert!(!shown(&downgrade, &major));
+        assert!(!shown(&downgrade, &minor));
+        assert!(shown(&downgrade, &downgrade));
+        assert!(!shown(&Some(ChangeType::Epoch), &downgrade));
+    }
+}